@@ -0,0 +1,191 @@
+use crate::cli::{self, CliError, Output};
+use crate::console;
+use crate::control;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tiny_http::{Header, Method, Request, Response, Server};
+use crossterm::style::Color;
+
+pub const DEFAULT_PORT: u16 = 8737;
+
+// Routes, all returning JSON:
+//   GET  /tases
+//   GET  /tases/<tas>/savestates
+//   GET  /tases/<tas>/attempts
+//   POST /tases/<tas>/savestates                   {"nickname": ..., "world": ...}
+//   POST /tases/<tas>/savestates/<savestate>/load
+// Every request needs an "Authorization: Bearer <token>" header. Requests
+// from browsers, which send an Origin header or a Host other than this
+// server, are refused so that web pages can't drive the API, and POST
+// bodies must be sent as application/json. Creating and loading in a TAS
+// that is open in an interactive session goes through that session.
+
+#[derive(Deserialize)]
+struct CreateBody {
+    nickname: String,
+    #[serde(default)]
+    world: Option<String>,
+}
+
+// A random token for when none is given. The standard library seeds
+// its hashers from the operating system's random number generator.
+pub fn generate_token() -> String {
+    let mut hasher = Sha256::new();
+    for _ in 0..4 {
+        let mut seed = RandomState::new().build_hasher();
+        seed.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos());
+        hasher.update(seed.finish().to_le_bytes());
+    }
+    hasher.finalize().iter().take(16).map(|b| format!("{:02x}", b)).collect()
+}
+
+// Serve the API on the loopback interface until the process is stopped.
+// Requests are handled one at a time. Without a token, one is generated.
+pub fn serve(port: u16, token: Option<String>) -> io::Result<()> {
+    let server = Server::http(("127.0.0.1", port)).map_err(|e| io::Error::other(e.to_string()))?;
+    console::write_line(&Color::Green, true, &format!("Listening on http://127.0.0.1:{}", port));
+    let token = match token {
+        Some(token) => token,
+        None => {
+            let token = generate_token();
+            console::write_line(&Color::Yellow, true, &format!("Send requests with the header Authorization: Bearer {}", token));
+            token
+        }
+    };
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = if !is_from_local_program(&request, port) {
+            (403, json!({ "error": "Requests from browsers are not accepted" }))
+        } else if !is_authorized(&request, &token) {
+            (401, json!({ "error": "Missing or wrong token" }))
+        } else if *request.method() == Method::Post && !is_json(&request) {
+            (415, json!({ "error": "POST requests must have Content-Type: application/json" }))
+        } else {
+            handle(&mut request)
+        };
+        console::write_line(&Color::Cyan, false, &format!("{} {} {}", request.method(), request.url(), status));
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body.to_string()).with_status_code(status).with_header(content_type);
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request.headers().iter().find(|header| header.field.equiv(name)).map(|header| header.value.as_str())
+}
+
+// Browsers add an Origin header to cross-site requests and send the
+// attacker's host name after DNS rebinding. Programs like curl do neither.
+fn is_from_local_program(request: &Request, port: u16) -> bool {
+    let allowed_hosts = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    header(request, "Origin").is_none() && header(request, "Host").is_some_and(|host| allowed_hosts.iter().any(|allowed| allowed == host))
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    header(request, "Authorization") == Some(format!("Bearer {}", token).as_str())
+}
+
+fn is_json(request: &Request) -> bool {
+    header(request, "Content-Type").is_some_and(|content_type| {
+        content_type.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case("application/json")
+    })
+}
+
+// Decode %XX escapes in a URL path segment, e.g. TAS names with spaces
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn status_code(error: &CliError) -> u16 {
+    match error.code {
+        cli::USAGE_ERROR => 400,
+        cli::NOT_FOUND => 404,
+        _ => 409,
+    }
+}
+
+// An interactive session keeps its TAS in memory and would overwrite
+// changes made to the TAS here the next time it saves it, so changes to
+// the TAS of a session are sent to the session's control socket instead.
+// Returns whether an interactive session has the TAS open.
+fn is_open_in_session(tas: &str) -> Result<bool, CliError> {
+    match control::send_command("status") {
+        None => Ok(false),
+        Some(Ok(status)) => Ok(status["result"]["tas"].as_str() == Some(tas)),
+        Some(Err(e)) => Err(cli::failed(&format!("Failed to reach the interactive session: {}", e))),
+    }
+}
+
+fn send_to_session(command: &str) -> Result<Output, CliError> {
+    // The socket reads one command per line
+    if command.contains(['\n', '\r']) {
+        return Err(CliError { code: cli::USAGE_ERROR, message: "Names can't contain line breaks".to_string() });
+    }
+    let reply = control::send_command(command)
+        .ok_or(cli::failed("The interactive session has closed"))?
+        .map_err(|e| cli::failed(&format!("Failed to reach the interactive session: {}", e)))?;
+    match reply["ok"].as_bool() {
+        Some(true) => Ok(Output { json: reply["result"].clone(), text: vec![] }),
+        _ => Err(cli::failed(reply["error"].as_str().unwrap_or("The interactive session failed"))),
+    }
+}
+
+fn handle(request: &mut Request) -> (u16, Value) {
+    let path = request.url().split('?').next().unwrap_or_default().to_string();
+    let segments: Vec<String> = path.split('/').filter(|s| !s.is_empty()).map(percent_decode).collect();
+    let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+
+    let result = match (request.method(), segments.as_slice()) {
+        (Method::Get, ["tases"]) => Ok(cli::list_tases()),
+        (Method::Get, ["tases", tas, "savestates"]) => cli::find_tas(tas).map(|tas| cli::list_savestates(&tas)),
+        (Method::Get, ["tases", tas, "attempts"]) => cli::find_tas(tas).map(|tas| Output { json: json!(tas.attempts), text: vec![] }),
+        (Method::Post, ["tases", tas, "savestates"]) => {
+            let body: CreateBody = match serde_json::from_reader(request.as_reader()) {
+                Ok(body) => body,
+                Err(e) => return (400, json!({ "error": format!("Invalid request body: {}", e) })),
+            };
+            match is_open_in_session(tas) {
+                // The session saves the world it loaded last
+                Ok(true) if body.world.is_some() => Err(cli::failed(&format!("{} is open in an interactive session, which can only save the world it loaded", tas))),
+                Ok(true) => send_to_session(&format!("create {}", body.nickname)),
+                Ok(false) => cli::find_tas(tas).and_then(|mut tas| cli::create(&mut tas, &body.nickname, body.world.as_deref())),
+                Err(e) => Err(e),
+            }
+        }
+        (Method::Post, ["tases", tas, "savestates", savestate, "load"]) => {
+            match is_open_in_session(tas) {
+                Ok(true) => send_to_session(&format!("load {}", savestate)),
+                Ok(false) => cli::find_tas(tas).and_then(|mut tas| cli::load(&mut tas, savestate)),
+                Err(e) => Err(e),
+            }
+        }
+        _ => return (404, json!({ "error": format!("No route for {} {}", request.method(), path) })),
+    };
+
+    match result {
+        Ok(output) => (200, output.json),
+        Err(error) => (status_code(&error), json!({ "error": error.message })),
+    }
+}
@@ -0,0 +1,212 @@
+use crate::console;
+use crate::control::SharedSession;
+use crate::server::{self, Target};
+use crate::tas::{self, Tas};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use fs_extra::dir::{self, CopyOptions};
+use chrono::{DateTime, Utc};
+use crossterm::style::Color;
+
+pub const DEFAULT_KEEP: usize = 6;
+// How often the background thread checks whether a snapshot is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+// Held while snapshots are taken and pruned and while one is promoted,
+// so the background thread can't prune a snapshot that is being copied
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+// An automatic snapshot of the world being played
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Autosave {
+    pub folder: String,
+    // Folder name of the world it was taken from
+    pub world: String,
+    pub created: DateTime<Utc>,
+}
+
+pub fn get_autosaves_folder(tas_folder: &Path) -> PathBuf {
+    tas_folder.join("autosaves")
+}
+
+fn get_log_path(tas_folder: &Path) -> PathBuf {
+    tas_folder.join("autosaves.json")
+}
+
+// Automatic snapshots of a TAS, oldest first
+pub fn get_autosaves(tas_folder: &Path) -> io::Result<Vec<Autosave>> {
+    match File::open(get_log_path(tas_folder)) {
+        Ok(file) => serde_json::from_reader(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn save_autosaves(tas_folder: &Path, autosaves: &[Autosave]) -> io::Result<()> {
+    let file = File::create(get_log_path(tas_folder))?;
+    serde_json::to_writer(file, autosaves).map_err(io::Error::other)
+}
+
+fn get_autosaves_or_report(tas_folder: &Path) -> Option<Vec<Autosave>> {
+    match get_autosaves(tas_folder) {
+        Ok(autosaves) => Some(autosaves),
+        Err(e) => {
+            console::write_line(&Color::Red, true, &format!("Failed to read {}: {}", get_log_path(tas_folder).display(), e));
+            None
+        }
+    }
+}
+
+pub fn get_autosave_path(tas: &Tas, autosave: &Autosave) -> PathBuf {
+    get_autosaves_folder(&tas.path).join(&autosave.folder)
+}
+
+fn last_modified(world: &Path) -> Option<DateTime<Utc>> {
+    let modified = world.join("level.dat").metadata().ok()?.modified().ok()?;
+    Some(modified.into())
+}
+
+// Copy the world and, for servers, its split dimension folders.
+// The game writes to the world while this runs, so errors are
+// returned instead of panicking.
+fn copy_live_world(tas: &Tas, world: &Path, to: &Path) -> Result<(), fs_extra::error::Error> {
+    let mut folders = vec![(world.to_path_buf(), to.to_path_buf())];
+    if tas.target == Target::Server {
+        for suffix in server::SPLIT_DIMENSIONS {
+            let split_folder = server::split_dimension_folder(world, suffix);
+            if split_folder.exists() {
+                folders.push((split_folder, to.join(suffix)));
+            }
+        }
+    }
+    let mut options = CopyOptions::new();
+    options.overwrite = true;
+    options.content_only = true;
+    let copy = || -> Result<(), fs_extra::error::Error> {
+        for (from, to) in folders {
+            std::fs::create_dir_all(&to)?;
+            dir::copy(from, to, &options)?;
+        }
+        Ok(())
+    };
+    match tas.target {
+        Target::Client => copy(),
+        Target::Server => server::with_saving_paused(&tas.minecraft_folder, copy),
+    }
+}
+
+// Snapshot a world into the autosaves folder, then drop the oldest
+// snapshots beyond the number to keep. Nothing is taken if the world
+// hasn't been saved by the game since the last snapshot of it, or
+// if the log of snapshots can't be read.
+pub fn snapshot(tas: &Tas, world: &Path) -> Option<Autosave> {
+    let _lock = LOG_LOCK.lock().unwrap();
+    let world_name = world.file_name()?.to_string_lossy().to_string();
+    let mut autosaves = get_autosaves_or_report(&tas.path)?;
+    let modified = last_modified(world)?;
+    if autosaves.iter().any(|a| a.world == world_name && a.created >= modified) {
+        return None;
+    }
+
+    // Folder names only go down to milliseconds, so later snapshots
+    // never share the time of the last one
+    let now = match autosaves.last() {
+        Some(last) => Utc::now().max(last.created + chrono::Duration::milliseconds(1)),
+        None => Utc::now(),
+    };
+    let folder = tas::sanitize_folder_name(&format!("{}-{}", now.format("%Y%m%d%H%M%S%3f"), world_name));
+    let autosave_folder = get_autosaves_folder(&tas.path).join(&folder);
+    if let Err(e) = copy_live_world(tas, world, &autosave_folder) {
+        console::write_line(&Color::Red, true, &format!("Failed to snapshot {}: {}", world_name, e));
+        let _ = std::fs::remove_dir_all(&autosave_folder);
+        return None;
+    }
+    let autosave = Autosave { folder, world: world_name, created: now };
+    autosaves.push(autosave.clone());
+
+    let excess = autosaves.len().saturating_sub(tas.autosave_keep);
+    let dropped: Vec<Autosave> = autosaves.drain(..excess).collect();
+    if let Err(e) = save_autosaves(&tas.path, &autosaves) {
+        console::write_line(&Color::Red, true, &format!("Failed to save the log of snapshots: {}", e));
+        let _ = std::fs::remove_dir_all(&autosave_folder);
+        return None;
+    }
+    for old in dropped {
+        let _ = std::fs::remove_dir_all(get_autosave_path(tas, &old));
+    }
+    Some(autosave)
+}
+
+// Turn an automatic snapshot into a real savestate, created at the
+// time of the snapshot
+pub fn promote(tas: &mut Tas, autosave: &Autosave, nickname: String) -> Option<PathBuf> {
+    let _lock = LOG_LOCK.lock().unwrap();
+    let folder = get_autosave_path(tas, autosave);
+    if !folder.exists() {
+        console::write_line(&Color::Red, true, &format!("The snapshot {} no longer exists", autosave.folder));
+        return None;
+    }
+    tas.create_savestate_from_snapshot(folder, nickname, autosave.created)
+}
+
+pub fn describe(autosave: &Autosave) -> String {
+    format!("{} {}", console::fit_width(&autosave.world, 34), autosave.created.format("%H:%M:%S %d/%m/%Y"))
+}
+
+pub fn describe_setting(tas: &Tas) -> String {
+    match tas.autosave_minutes {
+        Some(minutes) => format!("every {} minutes, keeping {}", minutes, tas.autosave_keep),
+        None => "off".to_string(),
+    }
+}
+
+// Snapshot the latest loaded world of the session every few minutes,
+// as set by the TAS, for as long as the program runs
+pub fn run_in_background(session: SharedSession) {
+    std::thread::spawn(move || {
+        let mut last_snapshot = Instant::now();
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            // Waits for any action the user is running in the menu, but
+            // copying doesn't hold up the menu
+            let (tas, world) = {
+                let session = session.lock().unwrap();
+                let Some(minutes) = session.tas.autosave_minutes else {
+                    continue;
+                };
+                if last_snapshot.elapsed() < Duration::from_secs(minutes * 60) {
+                    continue;
+                }
+                last_snapshot = Instant::now();
+                let Some(world) = session.latest_loaded_savestate.as_ref().filter(|world| world.exists()) else {
+                    continue;
+                };
+                (session.tas.clone(), world.clone())
+            };
+            if let Some(autosave) = snapshot(&tas, &world) {
+                console::write_line(&Color::DarkGrey, false, &format!("Automatically saved a snapshot of {}", autosave.world));
+            }
+        }
+    });
+}
+
+// Let the user pick an automatic snapshot and promote it to a savestate
+pub fn autosaves_menu(tas: &mut Tas) {
+    let Some(mut autosaves) = get_autosaves_or_report(&tas.path) else {
+        return;
+    };
+    if autosaves.is_empty() {
+        console::write_line(&Color::Yellow, true, &format!("No automatic snapshots yet ({})", describe_setting(tas)));
+        return;
+    }
+    autosaves.reverse();
+    let names: Vec<String> = autosaves.iter().map(describe).collect();
+    let choice = console::present_choices("Choose a snapshot to promote to a savestate".to_string(), names);
+    let nickname = console::get_input("Enter a nickname for the savestate: ");
+    if let Some(savestate) = promote(tas, &autosaves[choice], nickname) {
+        console::write_line(&Color::Green, true, &format!("Created savestate {}", savestate.file_name().unwrap().to_string_lossy()));
+    }
+}
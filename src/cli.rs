@@ -0,0 +1,453 @@
+use crate::api;
+use crate::autosave;
+use crate::console;
+use crate::dotfile;
+use crate::launch;
+use crate::livesplit;
+use crate::route;
+use crate::slots;
+use crate::server::{self, Target};
+use crate::stats;
+use crate::tas::{Savestate, Tas};
+use crate::worlds;
+use std::path::{Path, PathBuf};
+use serde_json::{json, Value};
+use chrono::{DateTime, Utc};
+use crossterm::style::Color;
+
+// Commands for scripts. With --json, results are printed to stdout as
+// JSON, errors as {"error": ...}, and all other output goes to stderr.
+pub const USAGE: &str = "Usage: savestates [--json] <command>
+
+Commands:
+  list tases
+  list savestates <tas>
+  list worlds [<tas>]
+  list minecraft-folders
+  create <tas> <nickname> [<world>]
+  load <tas> <savestate id or folder>
+  quick-save <tas>
+  load-latest <tas>
+  slots <tas>
+  save-slot <tas> <slot>
+  load-slot <tas> <slot>
+  revert-slot <tas> <slot>
+  delete <tas> <savestate id or folder>
+  autosaves <tas>
+  promote-autosave <tas> <snapshot folder> <nickname>
+  stats <tas>
+  route <tas>
+  load-next <tas>
+  promote <tas> <savestate id or folder> <segment>
+  export-splits <tas> <file>
+  import-splits <tas> <file>
+  serve [--port <port>] [--token <token>]";
+
+// Exit codes
+pub const FAILED: i32 = 1;
+pub const USAGE_ERROR: i32 = 2;
+pub const NOT_FOUND: i32 = 3;
+
+pub struct CliError {
+    pub code: i32,
+    pub message: String,
+}
+
+pub fn failed(message: &str) -> CliError {
+    CliError { code: FAILED, message: message.to_string() }
+}
+
+fn usage_error(message: &str) -> CliError {
+    CliError { code: USAGE_ERROR, message: message.to_string() }
+}
+
+fn not_found(message: &str) -> CliError {
+    CliError { code: NOT_FOUND, message: message.to_string() }
+}
+
+// The result of a command, as JSON and as lines of text
+pub struct Output {
+    pub json: Value,
+    pub text: Vec<String>,
+}
+
+// Run a command line and return the process exit code
+pub fn run(args: Vec<String>) -> i32 {
+    let json = args.iter().any(|arg| arg == "--json");
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).filter(|arg| *arg != "--json").collect();
+    console::use_stderr(json);
+    dotfile::create_dotfile_ifndef();
+
+    match run_command(&args) {
+        Ok(output) => {
+            if json {
+                println!("{}", output.json);
+            } else {
+                for line in output.text {
+                    console::write_line(&Color::Cyan, false, &line);
+                }
+            }
+            0
+        }
+        Err(error) => {
+            if json {
+                println!("{}", json!({ "error": error.message, "code": error.code }));
+            } else {
+                console::write_line(&Color::Red, true, &error.message);
+                if error.code == USAGE_ERROR {
+                    console::write_line(&Color::Cyan, false, USAGE);
+                }
+            }
+            error.code
+        }
+    }
+}
+
+fn run_command(args: &[&str]) -> Result<Output, CliError> {
+    match args {
+        ["list", "tases"] => Ok(list_tases()),
+        ["list", "savestates", tas] => Ok(list_savestates(&find_tas(tas)?)),
+        ["list", "worlds"] => Ok(list_worlds(None)),
+        ["list", "worlds", tas] => Ok(list_worlds(Some(&find_tas(tas)?))),
+        ["list", "minecraft-folders"] => Ok(list_minecraft_folders()),
+        ["create", tas, nickname] => create(&mut find_tas(tas)?, nickname, None),
+        ["create", tas, nickname, world] => create(&mut find_tas(tas)?, nickname, Some(world)),
+        ["load", tas, savestate] => load(&mut find_tas(tas)?, savestate),
+        ["quick-save", tas] => quick_save(&mut find_tas(tas)?),
+        ["load-latest", tas] => load_latest(&mut find_tas(tas)?),
+        ["slots", tas] => Ok(show_slots(&find_tas(tas)?)),
+        ["save-slot", tas, slot] => save_slot(&mut find_tas(tas)?, parse_slot(slot)?),
+        ["load-slot", tas, slot] => load_slot(&mut find_tas(tas)?, parse_slot(slot)?),
+        ["revert-slot", tas, slot] => revert_slot(&mut find_tas(tas)?, parse_slot(slot)?),
+        ["delete", tas, savestate] => delete(&mut find_tas(tas)?, savestate),
+        ["autosaves", tas] => list_autosaves(&find_tas(tas)?),
+        ["promote-autosave", tas, folder, nickname @ ..] if !nickname.is_empty() => promote_autosave(&mut find_tas(tas)?, folder, &nickname.join(" ")),
+        ["route", tas] => Ok(show_route(&find_tas(tas)?)),
+        ["load-next", tas] => load_next(&mut find_tas(tas)?),
+        ["promote", tas, savestate, segment] => promote(&mut find_tas(tas)?, savestate, segment),
+        ["stats", tas] => show_stats(&find_tas(tas)?),
+        ["export-splits", tas, file] => export_splits(&find_tas(tas)?, Path::new(file)),
+        ["import-splits", tas, file] => import_splits(&mut find_tas(tas)?, Path::new(file)),
+        ["serve", options @ ..] => serve(options),
+        [] => Err(usage_error("No command given")),
+        _ => Err(usage_error(&format!("Unknown command: {}", args.join(" ")))),
+    }
+}
+
+fn show_stats(tas: &Tas) -> Result<Output, CliError> {
+    let stats = stats::compute_stats(tas).map_err(|e| failed(&format!("Failed to read the attempts log: {}", e)))?;
+    Ok(Output {
+        json: json!(stats),
+        text: stats.iter().map(|s| format!(
+            "{} {} attempts {} resets {} completed, {} played",
+            s.savestate, s.attempts, s.resets, s.completions, stats::format_duration(s.seconds_played)
+        )).collect(),
+    })
+}
+
+fn show_route(tas: &Tas) -> Output {
+    let current = route::current_segment(tas);
+    Output {
+        json: Value::Array(tas.route.iter().enumerate().map(|(i, segment)| json!({
+            "name": segment.name,
+            "savestate": route::get_canonical(tas, segment).map(|info| savestate_json(tas, info)),
+            "current": current == Some(i),
+        })).collect()),
+        text: tas.route.iter().enumerate().map(|(i, segment)| format!("{}. {}", i + 1, route::describe_segment(tas, segment))).collect(),
+    }
+}
+
+fn load_next(tas: &mut Tas) -> Result<Output, CliError> {
+    let savestate = route::next_savestate(tas).map_err(|e| failed(&e))?;
+    load(tas, &savestate.file_name().unwrap().to_string_lossy())
+}
+
+fn promote(tas: &mut Tas, savestate: &str, segment: &str) -> Result<Output, CliError> {
+    let savestate = find_savestate(tas, savestate)?;
+    let id = tas.get_savestate_info(&savestate).unwrap().id;
+    route::promote(tas, id, segment).map_err(|e| failed(&e))?;
+    Ok(show_route(tas))
+}
+
+fn export_splits(tas: &Tas, file: &Path) -> Result<Output, CliError> {
+    livesplit::export_to_file(tas, file).map_err(|e| failed(&format!("Failed to export splits: {}", e)))?;
+    Ok(Output {
+        json: json!({ "file": file }),
+        text: vec![format!("Exported splits to {}", file.display())],
+    })
+}
+
+fn import_splits(tas: &mut Tas, file: &Path) -> Result<Output, CliError> {
+    let report = livesplit::import_from_file(tas, file).map_err(|e| failed(&format!("Failed to import splits: {}", e)))?;
+    let mut text = vec![format!("Updated the split times of {} savestates", report.updated)];
+    if !report.unmatched.is_empty() {
+        text.push(format!("No savestate named {}", report.unmatched.join(", ")));
+    }
+    Ok(Output {
+        json: json!({ "updated": report.updated, "unmatched": report.unmatched }),
+        text,
+    })
+}
+
+// Run the HTTP API until the process is stopped
+fn serve(options: &[&str]) -> Result<Output, CliError> {
+    let mut port = api::DEFAULT_PORT;
+    let mut token = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match (*option, options.next()) {
+            ("--port", Some(value)) => port = value.parse().map_err(|_| usage_error(&format!("Invalid port: {}", value)))?,
+            ("--token", Some(value)) => token = Some(value.to_string()),
+            _ => return Err(usage_error(&format!("Invalid option for serve: {}", option))),
+        }
+    }
+    api::serve(port, token).map_err(|e| failed(&format!("Failed to start the server: {}", e)))?;
+    Ok(Output { json: Value::Null, text: vec![] })
+}
+
+pub fn find_tas(name: &str) -> Result<Tas, CliError> {
+    let mut tas = dotfile::get_tases().into_iter().find(|tas| tas.name == name)
+        .ok_or(not_found(&format!("No TAS named {}", name)))?;
+    tas.adopt_untracked_savestates();
+    Ok(tas)
+}
+
+// Find a savestate by id, written as 3 or #3, or by folder name
+pub fn find_savestate(tas: &Tas, name: &str) -> Result<PathBuf, CliError> {
+    let info = match name.trim_start_matches('#').parse::<usize>() {
+        Ok(id) => tas.get_savestate_by_id(id),
+        Err(_) => tas.savestates.iter().find(|s| s.folder == name),
+    };
+    let info = info.ok_or(not_found(&format!("No savestate {} in {}", name, tas.name)))?;
+    Ok(tas.path.join("savestates").join(&info.folder))
+}
+
+fn modified(path: &Path) -> Option<DateTime<Utc>> {
+    path.metadata().and_then(|m| m.modified()).ok().map(|time| time.into())
+}
+
+fn savestate_json(tas: &Tas, info: &Savestate) -> Value {
+    json!({
+        "id": info.id,
+        "nickname": info.nickname,
+        "folder": info.folder,
+        "path": tas.path.join("savestates").join(&info.folder),
+        "created": info.created,
+        "base": info.base,
+        "partial": info.partial.is_some(),
+        "attempts": tas.attempts.get(&info.folder),
+        "players": info.players,
+    })
+}
+
+pub fn list_tases() -> Output {
+    let tases = dotfile::get_tases();
+    Output {
+        json: Value::Array(tases.iter().map(|tas| json!({
+            "name": tas.name,
+            "target": tas.target,
+            "minecraft_folder": tas.minecraft_folder,
+            "path": tas.path,
+            "savestates": tas.savestates.len(),
+            "attempts": tas.attempts.values().map(|attempt| attempt + 1).sum::<usize>(),
+        })).collect()),
+        text: tases.iter()
+            .map(|tas| format!("{} ({} savestates) {}", tas.name, tas.savestates.len(), tas.minecraft_folder.display()))
+            .collect(),
+    }
+}
+
+pub fn list_savestates(tas: &Tas) -> Output {
+    let savestates = tas.get_savestates();
+    Output {
+        json: Value::Array(savestates.iter()
+            .filter_map(|savestate| tas.get_savestate_info(savestate))
+            .map(|info| savestate_json(tas, info))
+            .collect()),
+        text: tas.format_names(&savestates),
+    }
+}
+
+fn world_json(world: &Path, minecraft_folder: &Path) -> Value {
+    json!({
+        "name": world.file_name().unwrap_or_default().to_string_lossy(),
+        "path": world,
+        "minecraft_folder": minecraft_folder,
+        "modified": modified(world),
+    })
+}
+
+// List the worlds of a TAS, or of every known .minecraft folder
+fn list_worlds(tas: Option<&Tas>) -> Output {
+    let mut found = vec![];
+    match tas {
+        Some(tas) if tas.target == Target::Server => {
+            let world = server::get_world(&tas.minecraft_folder);
+            if world.exists() {
+                found.push((world, tas.minecraft_folder.clone()));
+            }
+        }
+        Some(tas) => {
+            found.extend(worlds::get_all_worlds(&tas.minecraft_folder).into_iter().map(|world| (world, tas.minecraft_folder.clone())));
+        }
+        None => {
+            for folder in dotfile::get_minecraft_folders() {
+                found.extend(worlds::get_all_worlds(&folder).into_iter().map(|world| (world, folder.clone())));
+            }
+        }
+    }
+    Output {
+        json: Value::Array(found.iter().map(|(world, folder)| world_json(world, folder)).collect()),
+        text: found.iter().map(|(world, _)| world.display().to_string()).collect(),
+    }
+}
+
+fn list_minecraft_folders() -> Output {
+    let folders = dotfile::get_minecraft_folders();
+    Output {
+        json: json!(folders),
+        text: folders.iter().map(|folder| folder.display().to_string()).collect(),
+    }
+}
+
+// Find a world by path, or by name in the TAS's saves folder.
+// Server TASes default to the server's world.
+fn find_world(tas: &Tas, world: Option<&str>) -> Result<PathBuf, CliError> {
+    let world = match (world, tas.target) {
+        (Some(world), _) if Path::new(world).is_dir() => PathBuf::from(world),
+        (Some(world), Target::Client) => tas.minecraft_folder.join("saves").join(world),
+        (Some(world), Target::Server) => tas.minecraft_folder.join(world),
+        (None, Target::Server) => server::get_world(&tas.minecraft_folder),
+        (None, Target::Client) => return Err(usage_error("A world is required to create a savestate")),
+    };
+    if !world.is_dir() {
+        return Err(not_found(&format!("No world found at {}", world.display())));
+    }
+    Ok(world)
+}
+
+pub fn create(tas: &mut Tas, nickname: &str, world: Option<&str>) -> Result<Output, CliError> {
+    let world = find_world(tas, world)?;
+    let savestate = tas.create_savestate(world.clone(), nickname.to_string())
+        .ok_or(failed("The savestate was not created"))?;
+    let info = tas.get_savestate_info(&savestate).unwrap();
+    Ok(Output {
+        json: savestate_json(tas, info),
+        text: vec![format!("Created savestate #{} {} from {}", info.id, info.nickname, world.display())],
+    })
+}
+
+pub fn load(tas: &mut Tas, savestate: &str) -> Result<Output, CliError> {
+    let savestate = find_savestate(tas, savestate)?;
+    let world = tas.load_savestate(&savestate).ok_or(failed("The savestate was not loaded"))?;
+    let launched = match tas.target {
+        Target::Client => launch::launch_world(&tas.minecraft_folder, &world)
+            .map_err(|e| failed(&format!("Loaded into {}, but failed to launch the game: {}", world.display(), e)))?,
+        Target::Server => false,
+    };
+    let info = tas.get_savestate_info(&savestate).unwrap();
+    Ok(Output {
+        json: json!({
+            "savestate": savestate_json(tas, info),
+            "world": world,
+            "launched": launched,
+        }),
+        text: vec![format!("Loaded savestate #{} {} into {}", info.id, info.nickname, world.display())],
+    })
+}
+
+pub fn quick_save(tas: &mut Tas) -> Result<Output, CliError> {
+    let savestate = tas.quick_save().ok_or(failed("The savestate was not created"))?;
+    let info = tas.get_savestate_info(&savestate).unwrap();
+    Ok(Output {
+        json: savestate_json(tas, info),
+        text: vec![format!("Created savestate #{} {}", info.id, info.nickname)],
+    })
+}
+
+pub fn load_latest(tas: &mut Tas) -> Result<Output, CliError> {
+    let savestate = tas.get_latest_savestate().ok_or(not_found(&format!("No savestates in {}", tas.name)))?;
+    load(tas, &savestate.file_name().unwrap().to_string_lossy())
+}
+
+fn list_autosaves(tas: &Tas) -> Result<Output, CliError> {
+    let autosaves = autosave::get_autosaves(&tas.path).map_err(|e| failed(&format!("Failed to read the automatic snapshots: {}", e)))?;
+    Ok(Output {
+        json: Value::Array(autosaves.iter().map(|a| json!({
+            "folder": a.folder,
+            "world": a.world,
+            "created": a.created,
+            "path": autosave::get_autosave_path(tas, a),
+        })).collect()),
+        text: autosaves.iter().map(|a| format!("{} {}", a.folder, a.created.format("%H:%M:%S %d/%m/%Y"))).collect(),
+    })
+}
+
+fn promote_autosave(tas: &mut Tas, folder: &str, nickname: &str) -> Result<Output, CliError> {
+    let autosave = autosave::get_autosaves(&tas.path)
+        .map_err(|e| failed(&format!("Failed to read the automatic snapshots: {}", e)))?
+        .into_iter()
+        .find(|a| a.folder == folder)
+        .ok_or(not_found(&format!("No automatic snapshot {} in {}", folder, tas.name)))?;
+    let savestate = autosave::promote(tas, &autosave, nickname.to_string()).ok_or(failed("The savestate was not created"))?;
+    let info = tas.get_savestate_info(&savestate).unwrap();
+    Ok(Output {
+        json: savestate_json(tas, info),
+        text: vec![format!("Created savestate #{} {}", info.id, info.nickname)],
+    })
+}
+
+pub fn parse_slot(slot: &str) -> Result<u8, CliError> {
+    let slot = slot.parse::<u8>().map_err(|_| usage_error(&format!("Invalid slot: {}", slot)))?;
+    slots::check_slot(slot).map_err(|e| usage_error(&e))?;
+    Ok(slot)
+}
+
+fn slot_json(tas: &Tas, slot: u8) -> Value {
+    json!({
+        "slot": slot,
+        "savestate": slots::get_current(tas, slot).map(|info| savestate_json(tas, info)),
+        "history": tas.slots.get(&slot).cloned().unwrap_or_default(),
+    })
+}
+
+fn show_slots(tas: &Tas) -> Output {
+    Output {
+        json: Value::Array((0..slots::SLOT_COUNT).map(|slot| slot_json(tas, slot)).collect()),
+        text: (0..slots::SLOT_COUNT).map(|slot| slots::describe_slot(tas, slot)).collect(),
+    }
+}
+
+pub fn save_slot(tas: &mut Tas, slot: u8) -> Result<Output, CliError> {
+    slots::save_to_slot(tas, slot).map_err(|e| failed(&e))?;
+    Ok(Output {
+        json: slot_json(tas, slot),
+        text: vec![slots::describe_slot(tas, slot)],
+    })
+}
+
+pub fn load_slot(tas: &mut Tas, slot: u8) -> Result<Output, CliError> {
+    let savestate = slots::get_slot_path(tas, slot).map_err(|e| not_found(&e))?;
+    load(tas, &savestate.file_name().unwrap().to_string_lossy())
+}
+
+fn revert_slot(tas: &mut Tas, slot: u8) -> Result<Output, CliError> {
+    slots::revert_slot(tas, slot).map_err(|e| not_found(&e))?;
+    Ok(Output {
+        json: slot_json(tas, slot),
+        text: vec![slots::describe_slot(tas, slot)],
+    })
+}
+
+pub fn delete(tas: &mut Tas, savestate: &str) -> Result<Output, CliError> {
+    let savestate = find_savestate(tas, savestate)?;
+    let info = tas.get_savestate_info(&savestate).cloned().unwrap();
+    let trash_id = tas.delete_savestate(&savestate).ok_or(failed("The savestate was not deleted"))?;
+    Ok(Output {
+        json: json!({
+            "savestate": savestate_json(tas, &info),
+            "trash_id": trash_id,
+        }),
+        text: vec![format!("Moved savestate #{} {} to the trash", info.id, info.nickname)],
+    })
+}
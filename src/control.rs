@@ -0,0 +1,162 @@
+use crate::cli::{self, CliError};
+use crate::dotfile;
+use crate::server::{self, Target};
+use crate::tas::Tas;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use serde_json::{json, Value};
+
+// The state of an interactive session, shared with the control socket
+pub struct Session {
+    pub tas: Tas,
+    // The latest world loaded, so that it can be deleted if the
+    // user loads before creating a new savestate
+    pub latest_loaded_savestate: Option<PathBuf>,
+}
+
+pub type SharedSession = Arc<Mutex<Session>>;
+
+pub const COMMANDS: &str = "create <nickname>, quick-save, save-slot <slot>, load-slot <slot>, load latest, load <savestate id or folder>, status";
+
+pub fn get_socket_path() -> PathBuf {
+    dotfile::get_dotfile_path().join("control.sock")
+}
+
+fn status(session: &Session) -> Value {
+    let tas = &session.tas;
+    json!({
+        "tas": tas.name,
+        "target": tas.target,
+        "latest_loaded": session.latest_loaded_savestate,
+        "latest_savestate": tas.get_latest_savestate(),
+        "savestates": tas.savestates.len(),
+        "attempts": tas.attempts.values().map(|attempt| attempt + 1).sum::<usize>(),
+    })
+}
+
+// Savestate the world being played: the latest loaded world,
+// or the server's world for server TASes
+fn create(session: &mut Session, nickname: &str) -> Result<Value, CliError> {
+    let world = match session.tas.target {
+        Target::Server => server::get_world(&session.tas.minecraft_folder),
+        Target::Client => session.latest_loaded_savestate.clone()
+            .ok_or(CliError { code: cli::FAILED, message: "No world has been loaded in this session".to_string() })?,
+    };
+    let world = world.to_string_lossy().to_string();
+    Ok(cli::create(&mut session.tas, nickname, Some(&world))?.json)
+}
+
+fn loaded(session: &mut Session, output: cli::Output) -> Value {
+    if let Some(world) = output.json["world"].as_str() {
+        session.latest_loaded_savestate = Some(PathBuf::from(world));
+    }
+    output.json
+}
+
+fn load(session: &mut Session, savestate: &str) -> Result<Value, CliError> {
+    let output = match savestate {
+        "latest" => cli::load_latest(&mut session.tas)?,
+        _ => cli::load(&mut session.tas, savestate)?,
+    };
+    Ok(loaded(session, output))
+}
+
+fn load_slot(session: &mut Session, slot: u8) -> Result<Value, CliError> {
+    let output = cli::load_slot(&mut session.tas, slot)?;
+    Ok(loaded(session, output))
+}
+
+// Run one command line from the socket and return the JSON reply
+pub fn handle_command(session: &mut Session, line: &str) -> Value {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let result = match words.as_slice() {
+        ["status"] => Ok(status(session)),
+        ["create", nickname @ ..] if !nickname.is_empty() => create(session, &nickname.join(" ")),
+        ["quick-save"] => cli::quick_save(&mut session.tas).map(|output| output.json),
+        ["save-slot", slot] => cli::parse_slot(slot).and_then(|slot| cli::save_slot(&mut session.tas, slot)).map(|output| output.json),
+        ["load-slot", slot] => cli::parse_slot(slot).and_then(|slot| load_slot(session, slot)),
+        ["load", savestate] => load(session, savestate),
+        _ => Err(CliError { code: cli::USAGE_ERROR, message: format!("Unknown command, expected one of: {}", COMMANDS) }),
+    };
+    match result {
+        Ok(value) => json!({ "ok": true, "result": value }),
+        Err(error) => json!({ "ok": false, "error": error.message }),
+    }
+}
+
+// Listen for commands on ~/.savestates/control.sock in the background.
+// Each line received is a command, answered with one line of JSON.
+// Returns the socket path if listening started.
+#[cfg(unix)]
+pub fn listen(session: SharedSession) -> Option<PathBuf> {
+    use crate::console;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use crossterm::style::Color;
+
+    let path = get_socket_path();
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            console::write_line(&Color::Yellow, true, "Another session is already listening on the control socket");
+            return None;
+        }
+        // Left behind by a session that didn't exit cleanly
+        let _ = std::fs::remove_file(&path);
+    }
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            console::write_line(&Color::Yellow, true, &format!("Failed to open the control socket: {}", e));
+            return None;
+        }
+    };
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let Ok(reader) = stream.try_clone() else {
+                continue;
+            };
+            let mut writer = stream;
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                // Waits for any action the user is running in the menu
+                let reply = handle_command(&mut session.lock().unwrap(), &line);
+                if writeln!(writer, "{}", reply).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    Some(path)
+}
+
+#[cfg(not(unix))]
+pub fn listen(_session: SharedSession) -> Option<PathBuf> {
+    None
+}
+
+// Send a command to the interactive session listening on the control
+// socket and return its JSON reply. Returns None if no session is
+// listening.
+#[cfg(unix)]
+pub fn send_command(line: &str) -> Option<std::io::Result<Value>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let stream = UnixStream::connect(get_socket_path()).ok()?;
+    let send = || -> std::io::Result<Value> {
+        writeln!(&stream, "{}", line)?;
+        let mut reply = String::new();
+        BufReader::new(&stream).read_line(&mut reply)?;
+        Ok(serde_json::from_str(&reply)?)
+    };
+    Some(send())
+}
+
+#[cfg(not(unix))]
+pub fn send_command(_line: &str) -> Option<std::io::Result<Value>> {
+    None
+}
+
+pub fn stop_listening(path: &Path) {
+    let _ = std::fs::remove_file(path);
+}
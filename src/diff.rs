@@ -0,0 +1,216 @@
+use crate::console;
+use crate::nbt;
+use crate::region::{self, Region, RegionFormat, RawChunk};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crossterm::style::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl Change {
+    fn symbol(&self) -> (&'static str, Color) {
+        match self {
+            Change::Added => ("+", Color::Green),
+            Change::Removed => ("-", Color::Red),
+            Change::Modified => ("~", Color::Yellow),
+        }
+    }
+}
+
+// A file that differs between the two worlds, relative to the world folder
+pub struct FileChange {
+    pub path: PathBuf,
+    pub change: Change,
+}
+
+// A chunk that differs inside a region file, in absolute chunk coordinates
+pub struct ChunkChange {
+    pub region: PathBuf,
+    pub x: i32,
+    pub z: i32,
+    pub change: Change,
+}
+
+// A changed value inside an NBT file such as level.dat or player data
+pub struct FieldChange {
+    pub file: PathBuf,
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+pub struct WorldDiff {
+    pub files: Vec<FileChange>,
+    pub chunks: Vec<ChunkChange>,
+    pub fields: Vec<FieldChange>,
+}
+
+impl WorldDiff {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+// Files that change on every open and carry no world state
+fn is_ignored(path: &Path) -> bool {
+    path == Path::new("session.lock")
+}
+
+fn list_files(world: &Path) -> BTreeSet<PathBuf> {
+    WalkDir::new(world).into_iter().flatten()
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.path().strip_prefix(world).unwrap().to_path_buf())
+        .filter(|p| !is_ignored(p))
+        .collect()
+}
+
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+    Ok(std::fs::read(a)? == std::fs::read(b)?)
+}
+
+fn is_region_file(path: &Path) -> bool {
+    RegionFormat::from_path(path).is_some()
+}
+
+fn is_nbt_file(path: &Path) -> bool {
+    path == Path::new("level.dat")
+        || (path.extension().and_then(|e| e.to_str()) == Some("dat")
+            && matches!(path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()), Some("playerdata") | Some("players")))
+}
+
+// Read the timestamp and raw (still compressed) data of
+// every chunk in a region file, keyed by chunk index
+fn read_region_chunks(path: &Path) -> io::Result<HashMap<usize, (u32, RawChunk)>> {
+    let region = Region::open(path)?;
+    let mut chunks = HashMap::new();
+    for (x, z) in region.chunks() {
+        // Corrupt chunks are skipped rather than failing the whole diff
+        if let Ok(Some(raw)) = region.raw_chunk(x, z) {
+            chunks.insert(region::chunk_index(x, z), (region.timestamp(x, z), raw));
+        }
+    }
+    Ok(chunks)
+}
+
+fn diff_region(relative: &Path, a: Option<&Path>, b: Option<&Path>, out: &mut Vec<ChunkChange>) -> io::Result<()> {
+    let before = match a { Some(a) => read_region_chunks(a)?, None => HashMap::new() };
+    let after = match b { Some(b) => read_region_chunks(b)?, None => HashMap::new() };
+    let (region_x, region_z) = region::region_coordinates(relative).unwrap_or((0, 0));
+
+    let indices: BTreeSet<&usize> = before.keys().chain(after.keys()).collect();
+    for index in indices {
+        let change = match (before.get(index), after.get(index)) {
+            (Some(_), None) => Change::Removed,
+            (None, Some(_)) => Change::Added,
+            (Some(x), Some(y)) if x != y => Change::Modified,
+            _ => continue,
+        };
+        out.push(ChunkChange {
+            region: relative.to_path_buf(),
+            x: region_x * 32 + (index % 32) as i32,
+            z: region_z * 32 + (index / 32) as i32,
+            change,
+        });
+    }
+
+    Ok(())
+}
+
+fn flatten_nbt(path: Option<&Path>) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    if let Some(Ok((_, tag))) = path.map(nbt::read_file) {
+        tag.flatten("", &mut fields);
+    }
+    fields
+}
+
+fn diff_nbt(relative: &Path, a: Option<&Path>, b: Option<&Path>, out: &mut Vec<FieldChange>) {
+    let before = flatten_nbt(a);
+    let after = flatten_nbt(b);
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    for key in keys {
+        let (x, y) = (before.get(key), after.get(key));
+        if x != y {
+            out.push(FieldChange {
+                file: relative.to_path_buf(),
+                field: key.clone(),
+                before: x.cloned(),
+                after: y.cloned(),
+            });
+        }
+    }
+}
+
+// Compare two world folders, e.g. two savestates or
+// a savestate and a world in the saves folder
+pub fn diff_worlds(a: &Path, b: &Path) -> io::Result<WorldDiff> {
+    let files_a = list_files(a);
+    let files_b = list_files(b);
+    let mut diff = WorldDiff { files: vec![], chunks: vec![], fields: vec![] };
+
+    for relative in files_a.union(&files_b) {
+        let path_a = files_a.contains(relative).then(|| a.join(relative));
+        let path_b = files_b.contains(relative).then(|| b.join(relative));
+        let change = match (&path_a, &path_b) {
+            (Some(_), None) => Change::Removed,
+            (None, Some(_)) => Change::Added,
+            (Some(x), Some(y)) => {
+                if same_contents(x, y)? {
+                    continue;
+                }
+                Change::Modified
+            }
+            (None, None) => unreachable!(),
+        };
+        diff.files.push(FileChange { path: relative.clone(), change });
+
+        if is_region_file(relative) {
+            diff_region(relative, path_a.as_deref(), path_b.as_deref(), &mut diff.chunks)?;
+        } else if is_nbt_file(relative) {
+            diff_nbt(relative, path_a.as_deref(), path_b.as_deref(), &mut diff.fields);
+        }
+    }
+
+    Ok(diff)
+}
+
+// Print a diff, grouped into files, chunks and NBT fields
+pub fn print_diff(diff: &WorldDiff) {
+    if diff.is_empty() {
+        console::write_line(&Color::Green, true, "No differences found");
+        return;
+    }
+
+    console::write_line(&Color::Magenta, true, &format!("{} files changed", diff.files.len()));
+    for file in &diff.files {
+        let (symbol, color) = file.change.symbol();
+        console::write_line(&color, false, &format!("  {} {}", symbol, file.path.display()));
+    }
+
+    if !diff.chunks.is_empty() {
+        console::write_line(&Color::Magenta, true, &format!("{} chunks changed", diff.chunks.len()));
+        for chunk in &diff.chunks {
+            let (symbol, color) = chunk.change.symbol();
+            console::write_line(&color, false, &format!("  {} chunk {}, {} ({})", symbol, chunk.x, chunk.z, chunk.region.display()));
+        }
+    }
+
+    if !diff.fields.is_empty() {
+        console::write_line(&Color::Magenta, true, &format!("{} level.dat and player data fields changed", diff.fields.len()));
+        for field in &diff.fields {
+            let before = field.before.clone().unwrap_or("(none)".to_string());
+            let after = field.after.clone().unwrap_or("(none)".to_string());
+            console::write_line(&Color::Yellow, false, &format!("  {} {}: {} -> {}", field.file.display(), field.field, before, after));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::console;
+use std::path::Path;
+use std::process::Command;
+use serde::{Serialize, Deserialize};
+use crossterm::style::Color;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    PreCreate,
+    PostCreate,
+    PreLoad,
+    PostLoad,
+    PostDelete,
+}
+
+pub const EVENTS: [Event; 5] = [Event::PreCreate, Event::PostCreate, Event::PreLoad, Event::PostLoad, Event::PostDelete];
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::PreCreate => "pre_create",
+            Event::PostCreate => "post_create",
+            Event::PreLoad => "pre_load",
+            Event::PostLoad => "post_load",
+            Event::PostDelete => "post_delete",
+        }
+    }
+}
+
+// Shell commands run on savestate events. They get these variables:
+//   SAVESTATES_EVENT      the event name, e.g. post_load
+//   SAVESTATES_TAS        the TAS name
+//   SAVESTATES_SAVESTATE  the savestate folder, which for post_delete
+//                         is where it now is in the trash
+//   SAVESTATES_WORLD      the world saved or loaded, if known yet
+// A pre_ hook that fails cancels the operation.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Hooks {
+    #[serde(default)]
+    pub pre_create: Option<String>,
+    #[serde(default)]
+    pub post_create: Option<String>,
+    #[serde(default)]
+    pub pre_load: Option<String>,
+    #[serde(default)]
+    pub post_load: Option<String>,
+    #[serde(default)]
+    pub post_delete: Option<String>,
+}
+
+impl Hooks {
+    pub fn get(&self, event: Event) -> &Option<String> {
+        match event {
+            Event::PreCreate => &self.pre_create,
+            Event::PostCreate => &self.post_create,
+            Event::PreLoad => &self.pre_load,
+            Event::PostLoad => &self.post_load,
+            Event::PostDelete => &self.post_delete,
+        }
+    }
+
+    pub fn get_mut(&mut self, event: Event) -> &mut Option<String> {
+        match event {
+            Event::PreCreate => &mut self.pre_create,
+            Event::PostCreate => &mut self.post_create,
+            Event::PreLoad => &mut self.pre_load,
+            Event::PostLoad => &mut self.post_load,
+            Event::PostDelete => &mut self.post_delete,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        EVENTS.iter().filter(|event| self.get(**event).is_some()).count()
+    }
+
+    // Run the hook for an event and wait for it to finish.
+    // Returns false if the hook failed, true if it succeeded or there is none.
+    pub fn run(&self, event: Event, tas: &str, savestate: &Path, world: Option<&Path>) -> bool {
+        let Some(command) = self.get(event) else {
+            return true;
+        };
+        let mut shell = shell(command);
+        shell.env("SAVESTATES_EVENT", event.name())
+            .env("SAVESTATES_TAS", tas)
+            .env("SAVESTATES_SAVESTATE", savestate);
+        if let Some(world) = world {
+            shell.env("SAVESTATES_WORLD", world);
+        }
+        // Keep stdout clean for JSON output
+        if console::is_using_stderr() {
+            shell.stdout(std::io::stderr());
+        }
+        match shell.status() {
+            Ok(status) if status.success() => true,
+            Ok(status) => {
+                console::write_line(&Color::Red, true, &format!("The {} hook failed with {}", event.name(), status));
+                false
+            }
+            Err(e) => {
+                console::write_line(&Color::Red, true, &format!("Failed to run the {} hook: {}", event.name(), e));
+                false
+            }
+        }
+    }
+}
+
+// A command run through the system shell. Hooks are scripts written by
+// the user, so pipes and variables work in them. Nothing is filled into
+// the command, the values are passed in environment variables.
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
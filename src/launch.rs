@@ -0,0 +1,130 @@
+use crate::console;
+use crate::dotfile;
+use crate::tas;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use crossterm::style::Color;
+
+pub const PLACEHOLDERS: &str = "{world}, {path}, {instance}, {minecraft}";
+
+fn get_launch_commands_path() -> PathBuf {
+    dotfile::get_dotfile_path().join("launch.json")
+}
+
+// Launch commands by .minecraft folder, since each launcher instance
+// needs its own command. A file that can't be read is an error, so that
+// setting one command doesn't overwrite the others.
+pub fn get_launch_commands() -> io::Result<BTreeMap<PathBuf, String>> {
+    match File::open(get_launch_commands_path()) {
+        Ok(file) => serde_json::from_reader(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn get_launch_command(minecraft_folder: &Path) -> io::Result<Option<String>> {
+    Ok(get_launch_commands()?.remove(minecraft_folder))
+}
+
+pub fn set_launch_command(minecraft_folder: &Path, command: Option<String>) -> io::Result<()> {
+    let mut commands = get_launch_commands()?;
+    match command {
+        Some(command) => commands.insert(minecraft_folder.to_path_buf(), command),
+        None => commands.remove(minecraft_folder),
+    };
+    let file = File::create(get_launch_commands_path())?;
+    serde_json::to_writer(file, &commands).map_err(io::Error::other)
+}
+
+// Split a command into its program and arguments the way a shell would
+// for simple commands: whitespace separates arguments unless quoted with
+// " or ', and \ escapes a quote. Nothing else is special.
+pub fn split_arguments(command: &str) -> Result<Vec<String>, String> {
+    let mut arguments = vec![];
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', _) if matches!(chars.peek(), Some('"') | Some('\'')) => {
+                current.get_or_insert_with(String::new).push(chars.next().unwrap());
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, Some(_)) => current.get_or_insert_with(String::new).push(c),
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (c, None) if c.is_whitespace() => arguments.extend(current.take()),
+            (c, None) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("The command has an unclosed quote".to_string());
+    }
+    arguments.extend(current);
+    if arguments.is_empty() {
+        return Err("The command is empty".to_string());
+    }
+    Ok(arguments)
+}
+
+// Fill in a launch command for a world, placeholder by placeholder in
+// each argument. {instance} is the name of the folder holding the
+// .minecraft folder, which is what launchers such as Prism use to name
+// their instances. World names come from savestate nicknames, so the
+// command is never run through a shell.
+pub fn build_command(command: &str, minecraft_folder: &Path, world: &Path) -> Result<Command, String> {
+    let instance = minecraft_folder.parent()
+        .and_then(|parent| parent.file_name())
+        .unwrap_or_default()
+        .to_string_lossy();
+    let world_name = world.file_name().unwrap_or_default().to_string_lossy();
+    let values = [
+        ("world", world_name.as_ref()),
+        ("path", &world.display().to_string()),
+        ("instance", instance.as_ref()),
+        ("minecraft", &minecraft_folder.display().to_string()),
+    ];
+    let arguments: Vec<String> = split_arguments(command)?.iter()
+        .map(|argument| tas::fill_template(argument, &values))
+        .collect();
+    let mut command = Command::new(&arguments[0]);
+    command.args(&arguments[1..]).current_dir(minecraft_folder);
+    Ok(command)
+}
+
+// Start the game straight into a world with the launch command
+// configured for its .minecraft folder. Does nothing if there is none.
+pub fn launch_world(minecraft_folder: &Path, world: &Path) -> io::Result<bool> {
+    let Some(command) = get_launch_command(minecraft_folder)? else {
+        return Ok(false);
+    };
+    build_command(&command, minecraft_folder, world)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(true)
+}
+
+// Ask the user for the launch command of a .minecraft folder
+pub fn edit_launch_command(minecraft_folder: &Path) {
+    console::write_line(&Color::Cyan, false, &format!("Available placeholders: {}", PLACEHOLDERS));
+    console::write_line(&Color::Cyan, false, "e.g. prismlauncher --launch {instance} --world {world}");
+    console::write_line(&Color::Cyan, false, "The command is run directly, not through a shell, so pipes and variables don't work");
+    let command = console::get_input("Enter the command that starts the game in a loaded world (blank to turn off): ");
+    let command = Some(command).filter(|command| !command.is_empty());
+    if let Err(e) = set_launch_command(minecraft_folder, command.clone()) {
+        console::write_line(&Color::Red, true, &format!("Failed to save the launch command to {}: {}", get_launch_commands_path().display(), e));
+        return;
+    }
+    match &command {
+        Some(command) => console::write_line(&Color::Green, true, &format!("Loaded worlds will be launched with {}", command)),
+        None => console::write_line(&Color::Green, true, "Loaded worlds will not be launched"),
+    }
+}
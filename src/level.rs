@@ -0,0 +1,136 @@
+use crate::nbt::{self, Tag};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+fn missing(field: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("level.dat has no {} tag", field))
+}
+
+// Read, edit and write back a world's level.dat
+pub fn edit_level_dat<F>(world: &Path, edit: F) -> io::Result<()>
+where
+    F: FnOnce(&mut Tag) -> io::Result<()>,
+{
+    let path = world.join("level.dat");
+    let (name, mut root) = nbt::read_file(&path)?;
+    edit(&mut root)?;
+    nbt::write_file(&path, &name, &root)
+}
+
+// Set the name the game shows for a world in its world list
+pub fn set_level_name(world: &Path, level_name: &str) -> io::Result<()> {
+    edit_level_dat(world, |root| {
+        let data = root.get_mut("Data").ok_or_else(|| missing("Data"))?;
+        data.insert("LevelName", Tag::String(level_name.to_string()));
+        Ok(())
+    })
+}
+
+// Edits made to level.dat when a savestate is loaded. Unset
+// fields leave the copied value alone.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LoadPatch {
+    // Time of day in ticks, e.g. 0 for sunrise or 6000 for noon
+    pub day_time: Option<i64>,
+    pub clear_weather: bool,
+    pub gamerules: BTreeMap<String, String>,
+    pub health: Option<f32>,
+    pub food_level: Option<i32>,
+    pub teleport: Option<[f64; 3]>,
+}
+
+impl LoadPatch {
+    pub fn describe(&self) -> String {
+        let mut parts = vec![];
+        if let Some(day_time) = self.day_time {
+            parts.push(format!("time {}", day_time));
+        }
+        if self.clear_weather {
+            parts.push("clear weather".to_string());
+        }
+        for (rule, value) in &self.gamerules {
+            parts.push(format!("{} {}", rule, value));
+        }
+        if let Some(health) = self.health {
+            parts.push(format!("health {}", health));
+        }
+        if let Some(food_level) = self.food_level {
+            parts.push(format!("food {}", food_level));
+        }
+        if let Some([x, y, z]) = self.teleport {
+            parts.push(format!("teleport to {} {} {}", x, y, z));
+        }
+        if parts.is_empty() { "no changes".to_string() } else { parts.join(", ") }
+    }
+}
+
+// Replace a numeric tag's value, keeping the tag type the game version used
+fn set_number(parent: &mut Tag, key: &str, value: f64, default: Tag) {
+    let tag = match parent.get(key) {
+        Some(Tag::Byte(_)) => Tag::Byte(value as i8),
+        Some(Tag::Short(_)) => Tag::Short(value as i16),
+        Some(Tag::Int(_)) => Tag::Int(value as i32),
+        Some(Tag::Long(_)) => Tag::Long(value as i64),
+        Some(Tag::Float(_)) => Tag::Float(value as f32),
+        Some(Tag::Double(_)) => Tag::Double(value),
+        _ => default,
+    };
+    parent.insert(key, tag);
+}
+
+fn set_gamerule(rules: &mut Tag, rule: &str, value: &str) {
+    // Gamerules are stored as strings, except in versions that store typed values
+    let tag = match (rules.get(rule), value) {
+        (Some(Tag::Byte(_)), "true") => Tag::Byte(1),
+        (Some(Tag::Byte(_)), "false") => Tag::Byte(0),
+        (Some(Tag::Int(_)), v) if v.parse::<i32>().is_ok() => Tag::Int(v.parse().unwrap()),
+        _ => Tag::String(value.to_string()),
+    };
+    rules.insert(rule, tag);
+}
+
+// Apply a load patch to a world's level.dat
+pub fn apply_patch(world: &Path, patch: &LoadPatch) -> io::Result<()> {
+    edit_level_dat(world, |root| {
+        let data = root.get_mut("Data").ok_or_else(|| missing("Data"))?;
+
+        if let Some(day_time) = patch.day_time {
+            data.insert("DayTime", Tag::Long(day_time));
+        }
+        if patch.clear_weather {
+            data.insert("raining", Tag::Byte(0));
+            data.insert("thundering", Tag::Byte(0));
+            data.insert("rainTime", Tag::Int(0));
+            data.insert("thunderTime", Tag::Int(0));
+        }
+        if !patch.gamerules.is_empty() {
+            if data.get("GameRules").is_none() {
+                data.insert("GameRules", Tag::Compound(vec![]));
+            }
+            let rules = data.get_mut("GameRules").unwrap();
+            for (rule, value) in &patch.gamerules {
+                set_gamerule(rules, rule, value);
+            }
+        }
+
+        let changes_player = patch.health.is_some() || patch.food_level.is_some() || patch.teleport.is_some();
+        if !changes_player {
+            return Ok(());
+        }
+        let player = data.get_mut("Player").ok_or_else(|| missing("Data.Player"))?;
+        if let Some(health) = patch.health {
+            set_number(player, "Health", health as f64, Tag::Float(health));
+        }
+        if let Some(food_level) = patch.food_level {
+            player.insert("foodLevel", Tag::Int(food_level));
+        }
+        if let Some(position) = patch.teleport {
+            player.insert("Pos", Tag::List(6, position.iter().map(|v| Tag::Double(*v)).collect()));
+            player.insert("Motion", Tag::List(6, vec![Tag::Double(0.0); 3]));
+            player.insert("FallDistance", Tag::Float(0.0));
+        }
+        Ok(())
+    })
+}
@@ -1,4 +1,27 @@
-pub mod worlds;
-pub mod dotfile;
-pub mod console;
-pub mod tas;
\ No newline at end of file
+pub mod worlds;
+pub mod dotfile;
+pub mod console;
+pub mod tas;
+pub mod nbt;
+pub mod region;
+pub mod partial;
+pub mod verify;
+pub mod settings;
+pub mod usage;
+pub mod trash;
+pub mod oplog;
+pub mod level;
+pub mod players;
+pub mod server;
+pub mod rcon;
+pub mod launch;
+pub mod hooks;
+pub mod cli;
+pub mod api;
+pub mod control;
+pub mod stats;
+pub mod livesplit;
+pub mod route;
+pub mod slots;
+pub mod autosave;
+pub mod diff;
\ No newline at end of file
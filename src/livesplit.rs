@@ -0,0 +1,254 @@
+use crate::console;
+use crate::dotfile;
+use crate::route;
+use crate::stats;
+use crate::tas::{Savestate, Tas};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crossterm::style::Color;
+
+pub const GAME_NAME: &str = "Minecraft: Java Edition";
+const PERSONAL_BEST: &str = "Personal Best";
+
+// A segment read from a split file, with its cumulative personal best time
+pub struct Segment {
+    pub name: String,
+    pub split_seconds: Option<f64>,
+}
+
+pub struct ImportReport {
+    pub updated: usize,
+    pub unmatched: Vec<String>,
+}
+
+// Format seconds as a LiveSplit time, e.g. 01:02:03.4560000
+pub fn format_time(seconds: f64) -> String {
+    // LiveSplit counts in ticks of 100 nanoseconds
+    let ticks = (seconds.max(0.0) * 10_000_000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:07}",
+        ticks / 36_000_000_000,
+        ticks / 600_000_000 % 60,
+        ticks / 10_000_000 % 60,
+        ticks % 10_000_000
+    )
+}
+
+// Parse a LiveSplit time, e.g. 01:02:03.4560000, or 1.01:02:03 with days
+pub fn parse_time(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let (days, rest) = match (text.find('.'), text.find(':')) {
+        (Some(dot), Some(colon)) if dot < colon => (text[..dot].parse::<f64>().ok()?, &text[dot + 1..]),
+        _ => (0.0, text),
+    };
+    let mut seconds = 0.0;
+    for part in rest.split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(days * 86_400.0 + seconds)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+// Find every <tag ...>...</tag> in some XML, returning the attributes
+// and contents of each. Self-closing tags have empty contents.
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut found = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after_name = &rest[start + open.len()..];
+        // Skip longer tags with the same prefix, e.g. Segments for Segment
+        if !after_name.starts_with([' ', '>', '/']) {
+            rest = after_name;
+            continue;
+        }
+        let Some(end) = after_name.find('>') else {
+            break;
+        };
+        let attributes = &after_name[..end];
+        let body = &after_name[end + 1..];
+        if attributes.ends_with('/') {
+            found.push((attributes, ""));
+            rest = body;
+            continue;
+        }
+        let Some(body_end) = body.find(&close) else {
+            break;
+        };
+        found.push((attributes, &body[..body_end]));
+        rest = &body[body_end + close.len()..];
+    }
+    found
+}
+
+// The savestates of the route with their segment names. Without a
+// route, the segments are the longest chain of savestates each created
+// from the one before, named after the savestates. Retries that branch
+// off the chain aren't segments of the run.
+fn route_order(tas: &Tas) -> Vec<(String, &Savestate)> {
+    if !tas.route.is_empty() {
+        return tas.route.iter()
+            .filter_map(|segment| route::get_canonical(tas, segment).map(|info| (segment.name.clone(), info)))
+            .collect();
+    }
+    let mut savestates: Vec<&Savestate> = tas.savestates.iter().collect();
+    savestates.sort_by_key(|s| s.id);
+    let mut longest: Vec<&Savestate> = vec![];
+    for savestate in savestates {
+        let chain = tas.get_ancestry(savestate);
+        if chain.len() > longest.len() {
+            longest = chain;
+        }
+    }
+    longest.into_iter().map(|s| (s.nickname.clone(), s)).collect()
+}
+
+// Build a LiveSplit split file with one segment per savestate of the run.
+// Each segment takes as long as the attempt that created its savestate.
+pub fn export_lss(tas: &Tas) -> String {
+    let segments = route_order(tas);
+    // The best time reached from each savestate to any next one
+    let mut best: HashMap<&str, f64> = HashMap::new();
+    for s in &tas.savestates {
+        if let (Some(from), Some(seconds)) = (&s.created_from, s.segment_seconds) {
+            let entry = best.entry(from.as_str()).or_insert(seconds);
+            *entry = entry.min(seconds);
+        }
+    }
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<Run version=\"1.7.0\">\n");
+    xml.push_str("  <GameIcon />\n");
+    xml.push_str(&format!("  <GameName>{}</GameName>\n", escape(GAME_NAME)));
+    xml.push_str(&format!("  <CategoryName>{}</CategoryName>\n", escape(&tas.name)));
+    xml.push_str("  <Offset>00:00:00</Offset>\n");
+    xml.push_str(&format!("  <AttemptCount>{}</AttemptCount>\n", stats::get_attempts(&tas.path).map_or(0, |attempts| attempts.len())));
+    xml.push_str("  <AttemptHistory />\n");
+    xml.push_str("  <Segments>\n");
+    // Splits add up segment times, so once a segment has no time, no later
+    // split is known. The first savestate of a run, when it wasn't created
+    // from another, is where the run starts and takes no time.
+    let mut total = Some(0.0);
+    for (index, (name, s)) in segments.into_iter().enumerate() {
+        let split = total.zip(s.segment_seconds).map(|(total, seconds)| total + seconds);
+        if split.is_some() || !(index == 0 && s.created_from.is_none()) {
+            total = split;
+        }
+        xml.push_str("    <Segment>\n");
+        xml.push_str(&format!("      <Name>{}</Name>\n", escape(&name)));
+        xml.push_str("      <Icon />\n");
+        xml.push_str("      <SplitTimes>\n");
+        match split {
+            Some(split) => {
+                xml.push_str(&format!("        <SplitTime name=\"{}\">\n", PERSONAL_BEST));
+                xml.push_str(&format!("          <RealTime>{}</RealTime>\n", format_time(split)));
+                xml.push_str("        </SplitTime>\n");
+            }
+            None => xml.push_str(&format!("        <SplitTime name=\"{}\" />\n", PERSONAL_BEST)),
+        }
+        xml.push_str("      </SplitTimes>\n");
+        match s.created_from.as_deref().and_then(|from| best.get(from)) {
+            Some(best) => xml.push_str(&format!("      <BestSegmentTime>\n        <RealTime>{}</RealTime>\n      </BestSegmentTime>\n", format_time(*best))),
+            None => xml.push_str("      <BestSegmentTime />\n"),
+        }
+        xml.push_str("      <SegmentHistory />\n");
+        xml.push_str("    </Segment>\n");
+    }
+    xml.push_str("  </Segments>\n");
+    xml.push_str("  <AutoSplitterSettings />\n");
+    xml.push_str("</Run>\n");
+    xml
+}
+
+// Read the segments and personal best split times of a split file
+pub fn parse_lss(xml: &str) -> Vec<Segment> {
+    let Some((_, segments)) = elements(xml, "Segments").into_iter().next() else {
+        return vec![];
+    };
+    elements(segments, "Segment").into_iter().map(|(_, segment)| {
+        let name = elements(segment, "Name").first().map(|(_, name)| unescape(name)).unwrap_or_default();
+        let split_seconds = elements(segment, "SplitTime").into_iter()
+            .find(|(attributes, _)| attributes.contains(&format!("name=\"{}\"", PERSONAL_BEST)))
+            .and_then(|(_, split)| elements(split, "RealTime").first().and_then(|(_, time)| parse_time(time)));
+        Segment { name: name.trim().to_string(), split_seconds }
+    }).collect()
+}
+
+// Set segment times of savestates from split file segments, matching
+// them by route segment name, or by nickname if there is no route
+pub fn apply_segments(tas: &mut Tas, segments: &[Segment]) -> ImportReport {
+    let order: Vec<(String, usize)> = route_order(tas).into_iter().map(|(name, s)| (name, s.id)).collect();
+    let mut matched = vec![];
+    let mut report = ImportReport { updated: 0, unmatched: vec![] };
+    let mut previous_split = 0.0;
+    for segment in segments {
+        let segment_seconds = segment.split_seconds.map(|split| split - previous_split);
+        if let Some(split) = segment.split_seconds {
+            previous_split = split;
+        }
+        let id = order.iter()
+            .find(|(name, id)| !matched.contains(id) && *name == segment.name)
+            .map(|(_, id)| *id);
+        match id {
+            Some(id) => {
+                matched.push(id);
+                if let Some(seconds) = segment_seconds {
+                    tas.savestates.iter_mut().find(|s| s.id == id).unwrap().segment_seconds = Some(seconds);
+                    report.updated += 1;
+                }
+            }
+            None => report.unmatched.push(segment.name.clone()),
+        }
+    }
+    report
+}
+
+pub fn export_to_file(tas: &Tas, path: &Path) -> std::io::Result<()> {
+    std::fs::write(path, export_lss(tas))
+}
+
+pub fn import_from_file(tas: &mut Tas, path: &Path) -> std::io::Result<ImportReport> {
+    let xml = std::fs::read_to_string(path)?;
+    let report = apply_segments(tas, &parse_lss(&xml));
+    dotfile::update_tas(tas);
+    Ok(report)
+}
+
+pub fn print_import_report(report: &ImportReport) {
+    console::write_line(&Color::Green, true, &format!("Updated the split times of {} savestates", report.updated));
+    if !report.unmatched.is_empty() {
+        console::write_line(&Color::Yellow, true, &format!("No savestate named {}", report.unmatched.join(", ")));
+    }
+}
+
+// Let the user export or import a split file
+pub fn splits_menu(tas: &mut Tas) {
+    let choices = vec![
+        "Export savestates as LiveSplit splits".to_string(),
+        "Import split times from LiveSplit splits".to_string(),
+    ];
+    let default_path = tas.path.join(format!("{}.lss", tas.name));
+    let prompt = format!("Enter the path of the .lss file (blank for {}): ", default_path.display());
+    let choice = console::present_choices("Choose a splits action".to_string(), choices);
+    let input = console::get_input(&prompt);
+    let path = if input.is_empty() { default_path } else { PathBuf::from(input) };
+    match choice {
+        0 => match export_to_file(tas, &path) {
+            Ok(()) => console::write_line(&Color::Green, true, &format!("Exported {} segments to {}", route_order(tas).len(), path.display())),
+            Err(e) => console::write_line(&Color::Red, true, &format!("Failed to export splits: {}", e)),
+        },
+        _ => match import_from_file(tas, &path) {
+            Ok(report) => print_import_report(&report),
+            Err(e) => console::write_line(&Color::Red, true, &format!("Failed to import splits: {}", e)),
+        },
+    }
+}
@@ -1,81 +1,276 @@
-use std::path::PathBuf;
-
-use savestates::worlds;
-use savestates::dotfile;
-use savestates::console;
-use savestates::tas::{self, Tas};
-
-use crossterm::style::Color;
-
-fn main() {
-    dotfile::create_dotfile_ifndef();
-    
-    let mut tas: Tas = tas::choose_tas();
-    // Store the latest savestate loaded, so that we can
-    // delete it if the user loads before creating a new one
-    let mut latest_loaded_savestate: Option<PathBuf> = None;
-    
-    loop {
-        let choices = vec![
-            "Create a new savestate".to_string(),
-            "Load a savestate".to_string(),
-            "Delete a savestate".to_string(),
-            "Choose another TAS file".to_string(),
-            "Exit".to_string(),
-        ];
-        let choice = console::present_choices("Choose an action".to_string(), choices);
-        match choice {
-            0 => {
-                let world: PathBuf = worlds::choose_world(tas.minecraft_folder.clone());
-                let nickname = console::get_input("Enter a nickname for the savestate: ");
-                tas.create_savestate(world, nickname);
-
-            }
-            1 => {
-                match tas.choose_savestate() {
-                    Some(savestate) => {
-                        let new_world = tas.load_savestate(&savestate);
-                        if latest_loaded_savestate.is_some() {
-                            let confirmation = console::confirm("Do you want to delete the previously loaded savestate?".to_string(), "y");
-                            if confirmation {
-                                console::write_line(&Color::Yellow, true, &format!("Deleting the previously loaded savestate world {}", latest_loaded_savestate.clone().unwrap().file_name().unwrap().to_string_lossy()));
-                                worlds::delete_world(latest_loaded_savestate.unwrap());
-                            } else {
-                                console::write_line(&Color::Yellow, true, "Previous savestate not deleted");
-                            }
-                        }
-                        latest_loaded_savestate = Some(new_world.clone());
-                        console::write_line(&Color::Green, true, &format!("Savestate {} loaded successfully", savestate.file_name().unwrap().to_string_lossy()));
-                    }
-                    None => {}
-                }
-            }
-            2 => {
-                let mut savestates = tas.get_savestates();
-                if savestates.is_empty() {
-                    console::write_line(&Color::Red, true, "No savestates found");
-                    continue;
-                }
-
-                let savestate_names = Tas::format_names(&savestates);
-                let savestate_choice = console::present_choices("Choose a savestate to delete".to_string(), savestate_names);
-                let savestate = &savestates[savestate_choice];
-                let confirmation = console::confirm(format!("Are you sure you want to delete the savestate {}?", savestate.file_name().unwrap().to_string_lossy()), "delete");
-                if confirmation {
-                    tas.delete_savestate(savestate);
-                } else {
-                    console::write_line(&Color::Yellow, true, "Savestate deletion cancelled");
-                }
-            }
-            3 => {
-                tas = tas::choose_tas();
-            }
-            4 => {
-                break;
-            }
-            _ => {
-                console::write_line(&Color::Red, true, "Invalid choice");
-            }
-        }
-    }
-}
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use savestates::worlds;
+use savestates::dotfile;
+use savestates::console;
+use savestates::diff;
+use savestates::partial;
+use savestates::oplog;
+use savestates::players;
+use savestates::settings;
+use savestates::trash;
+use savestates::usage;
+use savestates::livesplit;
+use savestates::stats::{self, EndReason};
+use savestates::verify;
+use savestates::launch;
+use savestates::cli;
+use savestates::server::Target;
+use savestates::tas::{self, Tas};
+use savestates::route;
+use savestates::slots;
+use savestates::autosave;
+use savestates::control::{self, Session};
+
+use crossterm::style::Color;
+
+// Load a savestate, offer to delete the previously loaded world
+// and launch the game if a launch command is set
+fn load(tas: &mut Tas, savestate: &Path, latest_loaded_savestate: &mut Option<PathBuf>) {
+    let Some(new_world) = tas.load_savestate(savestate) else {
+        return;
+    };
+    // Loading in place reuses the same world, so there is nothing to delete
+    if let Some(previous_world) = latest_loaded_savestate.take().filter(|world| *world != new_world) {
+        let confirmation = console::confirm("Do you want to delete the previously loaded savestate?".to_string(), "y");
+        if confirmation {
+            console::write_line(&Color::Yellow, true, &format!("Moving the previously loaded savestate world {} to the trash", previous_world.file_name().unwrap().to_string_lossy()));
+            worlds::delete_world(previous_world);
+        } else {
+            console::write_line(&Color::Yellow, true, "Previous savestate not deleted");
+        }
+    }
+    *latest_loaded_savestate = Some(new_world.clone());
+    console::write_line(&Color::Green, true, &format!("Savestate {} loaded successfully", savestate.file_name().unwrap().to_string_lossy()));
+    if tas.target == Target::Client {
+        match launch::launch_world(&tas.minecraft_folder, &new_world) {
+            Ok(true) => console::write_line(&Color::Green, true, &format!("Launching {}", new_world.file_name().unwrap().to_string_lossy())),
+            Ok(false) => {}
+            Err(e) => console::write_line(&Color::Red, true, &format!("Failed to launch the game: {}", e)),
+        }
+    }
+}
+
+fn main() {
+    // Any arguments run a single command instead of the interactive menu
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(args));
+    }
+
+    dotfile::create_dotfile_ifndef();
+    match trash::purge_older_than(trash::DEFAULT_RETENTION_DAYS) {
+        Ok(0) => {}
+        Ok(purged) => console::write_line(&Color::Yellow, true, &format!("Permanently deleted {} items older than {} days from the trash", purged, trash::DEFAULT_RETENTION_DAYS)),
+        Err(e) => console::write_line(&Color::Red, true, &format!("Failed to read the trash, nothing was purged: {}", e)),
+    }
+    
+    let shared_session = Arc::new(Mutex::new(Session {
+        tas: tas::choose_tas(),
+        latest_loaded_savestate: None,
+    }));
+    stats::abandon_attempt(&shared_session.lock().unwrap().tas.path);
+    autosave::run_in_background(shared_session.clone());
+    let socket = control::listen(shared_session.clone());
+    if let Some(socket) = &socket {
+        console::write_line(&Color::Cyan, false, &format!("Listening for {} on {}", control::COMMANDS, socket.display()));
+    }
+    
+    loop {
+        let choices = vec![
+            "Quick save the latest world".to_string(),
+            "Load the latest savestate".to_string(),
+            "Save to or load a numbered slot".to_string(),
+            "Create a new savestate".to_string(),
+            "Create a partial savestate of selected dimensions or regions".to_string(),
+            "Load a savestate".to_string(),
+            "Delete a savestate".to_string(),
+            "Undo the last create, load or delete".to_string(),
+            "Compare a savestate with another savestate or world".to_string(),
+            "Verify savestates".to_string(),
+            "List players in a savestate".to_string(),
+            "Show disk usage".to_string(),
+            "Show attempt statistics".to_string(),
+            "Export or import LiveSplit splits".to_string(),
+            "Route".to_string(),
+            "Promote an automatic snapshot to a savestate".to_string(),
+            "Trash".to_string(),
+            "TAS settings".to_string(),
+            "Choose another TAS file".to_string(),
+            "Exit".to_string(),
+        ];
+        let choice = console::present_choices("Choose an action".to_string(), choices);
+        // Hold the session while the action runs, so that commands
+        // from the control socket wait until it is done
+        let mut session = shared_session.lock().unwrap();
+        let Session { tas, latest_loaded_savestate } = &mut *session;
+        match choice {
+            0 => {
+                tas.quick_save();
+            }
+            1 => {
+                match tas.get_latest_savestate() {
+                    Some(savestate) => load(tas, &savestate, latest_loaded_savestate),
+                    None => console::write_line(&Color::Red, true, "No savestates found"),
+                }
+            }
+            2 => {
+                if let Some(savestate) = slots::slots_menu(tas) {
+                    load(tas, &savestate, latest_loaded_savestate);
+                }
+            }
+            3 => {
+                let world: PathBuf = tas.choose_world();
+                let nickname = console::get_input("Enter a nickname for the savestate: ");
+                tas.create_savestate(world, nickname);
+
+            }
+            4 => {
+                let Some(base) = tas.choose_savestate("Choose the savestate to build on") else {
+                    continue;
+                };
+                let Some(base_id) = tas.get_savestate_info(&base).map(|info| info.id) else {
+                    continue;
+                };
+                let world: PathBuf = tas.choose_world();
+                let spec = partial::prompt_partial_spec();
+                let nickname = console::get_input("Enter a nickname for the savestate: ");
+                tas.create_partial_savestate(world, nickname, base_id, spec);
+            }
+            5 => {
+                if let Some(savestate) = tas.choose_savestate("Choose a savestate to load") {
+                    load(tas, &savestate, latest_loaded_savestate);
+                }
+            }
+            6 => {
+                let savestates = tas.get_savestates();
+                if savestates.is_empty() {
+                    console::write_line(&Color::Red, true, "No savestates found");
+                    continue;
+                }
+
+                let savestate_names = tas.format_names(&savestates);
+                let savestate_choice = console::present_choices("Choose a savestate to delete".to_string(), savestate_names);
+                let savestate = &savestates[savestate_choice];
+                let savestate_name = match tas.get_savestate_info(savestate) {
+                    Some(info) => format!("#{} {}", info.id, info.nickname),
+                    None => savestate.file_name().unwrap().to_string_lossy().to_string(),
+                };
+                let confirmation = console::confirm(format!("Are you sure you want to delete the savestate {}?", savestate_name), "delete");
+                if confirmation {
+                    tas.delete_savestate(savestate);
+                } else {
+                    console::write_line(&Color::Yellow, true, "Savestate deletion cancelled");
+                }
+            }
+            7 => {
+                oplog::undo_last(tas);
+                // Forget the loaded world if the undo removed it
+                if latest_loaded_savestate.as_ref().is_some_and(|world| !world.exists()) {
+                    *latest_loaded_savestate = None;
+                }
+            }
+            8 => {
+                let Some(before) = tas.choose_savestate("Choose the savestate to compare from") else {
+                    continue;
+                };
+                let targets = vec![
+                    "Another savestate".to_string(),
+                    "A world in the .minecraft saves folder or server".to_string(),
+                ];
+                let after = match console::present_choices("Compare it with".to_string(), targets) {
+                    0 => match tas.choose_savestate("Choose the savestate to compare to") {
+                        Some(savestate) => savestate,
+                        None => continue,
+                    },
+                    _ => tas.choose_world(),
+                };
+                match diff::diff_worlds(&before, &after) {
+                    Ok(world_diff) => diff::print_diff(&world_diff),
+                    Err(e) => console::write_line(&Color::Red, true, &format!("Failed to compare worlds: {}", e)),
+                }
+            }
+            9 => {
+                let choices = vec![
+                    "Verify one savestate".to_string(),
+                    "Verify every savestate in this TAS".to_string(),
+                    "Record checksums of an adopted savestate".to_string(),
+                ];
+                match console::present_choices("Choose what to verify".to_string(), choices) {
+                    0 => {
+                        if let Some(savestate) = tas.choose_savestate("Choose a savestate to verify") {
+                            let name = savestate.file_name().unwrap().to_string_lossy().to_string();
+                            verify::print_report(&name, &verify::verify_savestate(&tas.path, &savestate));
+                        }
+                    }
+                    1 => {
+                        if tas.verify_all() {
+                            console::write_line(&Color::Green, true, "All savestates passed verification");
+                        }
+                    }
+                    _ => {
+                        let adopted: Vec<PathBuf> = tas.get_savestates().into_iter()
+                            .filter(|s| tas.get_savestate_info(s).is_some_and(|info| info.unverified_baseline))
+                            .collect();
+                        if adopted.is_empty() {
+                            console::write_line(&Color::Yellow, true, "Every savestate already has a checksum baseline");
+                            continue;
+                        }
+                        console::write_line(&Color::Cyan, false, "Adopted savestates may already be damaged, check them in game before recording");
+                        let choice = console::present_choices("Choose a savestate to record".to_string(), tas.format_names(&adopted));
+                        match tas.record_baseline(&adopted[choice]) {
+                            Ok(()) => console::write_line(&Color::Green, true, "Checksums recorded"),
+                            Err(e) => console::write_line(&Color::Red, true, &format!("Failed to record checksums: {}", e)),
+                        }
+                    }
+                }
+            }
+            10 => {
+                if let Some(savestate) = tas.choose_savestate("Choose a savestate to list players of") {
+                    players::print_players(&savestate);
+                }
+            }
+            11 => {
+                usage::print_usage();
+            }
+            12 => {
+                stats::print_stats(tas);
+            }
+            13 => {
+                livesplit::splits_menu(tas);
+            }
+            14 => {
+                if let Some(savestate) = route::route_menu(tas) {
+                    load(tas, &savestate, latest_loaded_savestate);
+                }
+            }
+            15 => {
+                autosave::autosaves_menu(tas);
+            }
+            16 => {
+                trash::trash_menu(tas);
+            }
+            17 => {
+                settings::edit_settings(tas);
+            }
+            18 => {
+                stats::end_attempt(&tas.path, EndReason::Exit);
+                *tas = tas::choose_tas();
+                // The loaded world belongs to the previous TAS
+                *latest_loaded_savestate = None;
+                stats::abandon_attempt(&tas.path);
+            }
+            19 => {
+                stats::end_attempt(&tas.path, EndReason::Exit);
+                if let Some(socket) = &socket {
+                    control::stop_listening(socket);
+                }
+                break;
+            }
+            _ => {
+                console::write_line(&Color::Red, true, "Invalid choice");
+            }
+        }
+    }
+}
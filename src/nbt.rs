@@ -0,0 +1,367 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::path::Path;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+// A single NBT tag. Compounds keep their entries in file order
+// so that a file can be read, edited and written back unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    // Element type id and elements. The id is kept for empty lists.
+    List(u8, Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_, _) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    // Get a child of a compound tag by name
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    // Get a nested compound child by a dot separated path, e.g. "Data.Player"
+    pub fn get_path(&self, path: &str) -> Option<&Tag> {
+        path.split('.').try_fold(self, |tag, key| tag.get(key))
+    }
+
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Tag> {
+        path.split('.').try_fold(self, |tag, key| tag.get_mut(key))
+    }
+
+    // Insert or replace a child of a compound tag.
+    // Does nothing if the tag is not a compound.
+    pub fn insert(&mut self, key: &str, value: Tag) {
+        if let Tag::Compound(entries) = self {
+            match entries.iter_mut().find(|(k, _)| k == key) {
+                Some((_, v)) => *v = value,
+                None => entries.push((key.to_string(), value)),
+            }
+        }
+    }
+
+    // Remove a child of a compound tag, returning it if it existed
+    pub fn remove(&mut self, key: &str) -> Option<Tag> {
+        match self {
+            Tag::Compound(entries) => {
+                let index = entries.iter().position(|(k, _)| k == key)?;
+                Some(entries.remove(index).1)
+            }
+            _ => None,
+        }
+    }
+
+    // Flatten a tag into "path -> value" pairs, one per leaf value.
+    // List and array elements are addressed as path[i].
+    pub fn flatten(&self, prefix: &str, out: &mut BTreeMap<String, String>) {
+        match self {
+            Tag::Compound(entries) => {
+                for (key, value) in entries {
+                    let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    value.flatten(&path, out);
+                }
+            }
+            Tag::List(_, values) => {
+                for (i, value) in values.iter().enumerate() {
+                    value.flatten(&format!("{}[{}]", prefix, i), out);
+                }
+            }
+            Tag::Byte(v) => { out.insert(prefix.to_string(), v.to_string()); }
+            Tag::Short(v) => { out.insert(prefix.to_string(), v.to_string()); }
+            Tag::Int(v) => { out.insert(prefix.to_string(), v.to_string()); }
+            Tag::Long(v) => { out.insert(prefix.to_string(), v.to_string()); }
+            Tag::Float(v) => { out.insert(prefix.to_string(), v.to_string()); }
+            Tag::Double(v) => { out.insert(prefix.to_string(), v.to_string()); }
+            Tag::String(v) => { out.insert(prefix.to_string(), format!("{:?}", v)); }
+            Tag::ByteArray(v) => { out.insert(prefix.to_string(), format!("{:?}", v)); }
+            Tag::IntArray(v) => { out.insert(prefix.to_string(), format!("{:?}", v)); }
+            Tag::LongArray(v) => { out.insert(prefix.to_string(), format!("{:?}", v)); }
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// Lengths come from the file, so the buffer only grows as data
+// actually arrives instead of being allocated up front
+fn read_bytes<R: Read>(reader: &mut R, n: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(n as u64).read_to_end(&mut buf)?;
+    if buf.len() < n {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "NBT data ends early"));
+    }
+    Ok(buf)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    Ok(read_bytes(reader, 1)?[0])
+}
+
+fn read_i16<R: Read>(reader: &mut R) -> io::Result<i16> {
+    Ok(i16::from_be_bytes(read_bytes(reader, 2)?.try_into().unwrap()))
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> io::Result<i32> {
+    Ok(i32::from_be_bytes(read_bytes(reader, 4)?.try_into().unwrap()))
+}
+
+fn read_i64<R: Read>(reader: &mut R) -> io::Result<i64> {
+    Ok(i64::from_be_bytes(read_bytes(reader, 8)?.try_into().unwrap()))
+}
+
+fn read_length<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let length = read_i32(reader)?;
+    if length < 0 {
+        return Err(invalid("Negative NBT length"));
+    }
+    Ok(length as usize)
+}
+
+// NBT strings are Java's modified UTF-8: UTF-16 code units encoded
+// in one to three bytes each, with NUL as two bytes so that no byte is 0
+// and characters outside the BMP as two three-byte surrogates
+pub fn decode_modified_utf8(bytes: &[u8]) -> io::Result<String> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    let continuation = |i: usize| match bytes.get(i) {
+        Some(b) if b & 0xc0 == 0x80 => Ok((b & 0x3f) as u16),
+        _ => Err(invalid("Invalid modified UTF-8 in NBT string")),
+    };
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b & 0x80 == 0 {
+            units.push(b as u16);
+            i += 1;
+        } else if b & 0xe0 == 0xc0 {
+            units.push(((b & 0x1f) as u16) << 6 | continuation(i + 1)?);
+            i += 2;
+        } else if b & 0xf0 == 0xe0 {
+            units.push(((b & 0x0f) as u16) << 12 | continuation(i + 1)? << 6 | continuation(i + 2)?);
+            i += 3;
+        } else {
+            return Err(invalid("Invalid modified UTF-8 in NBT string"));
+        }
+    }
+    String::from_utf16(&units).map_err(|_| invalid("Unpaired surrogate in NBT string"))
+}
+
+pub fn encode_modified_utf8(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x01..=0x7f => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | (unit >> 6 & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    bytes
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let length = read_i16(reader)? as u16 as usize;
+    decode_modified_utf8(&read_bytes(reader, length)?)
+}
+
+fn read_payload<R: Read>(reader: &mut R, id: u8, depth: usize) -> io::Result<Tag> {
+    if depth > 512 {
+        return Err(invalid("NBT nested too deeply"));
+    }
+    Ok(match id {
+        1 => Tag::Byte(read_u8(reader)? as i8),
+        2 => Tag::Short(read_i16(reader)?),
+        3 => Tag::Int(read_i32(reader)?),
+        4 => Tag::Long(read_i64(reader)?),
+        5 => Tag::Float(f32::from_bits(read_i32(reader)? as u32)),
+        6 => Tag::Double(f64::from_bits(read_i64(reader)? as u64)),
+        7 => {
+            let length = read_length(reader)?;
+            Tag::ByteArray(read_bytes(reader, length)?.into_iter().map(|b| b as i8).collect())
+        }
+        8 => Tag::String(read_string(reader)?),
+        9 => {
+            let element_id = read_u8(reader)?;
+            let length = read_length(reader)?;
+            let mut values = Vec::new();
+            for _ in 0..length {
+                values.push(read_payload(reader, element_id, depth + 1)?);
+            }
+            Tag::List(element_id, values)
+        }
+        10 => {
+            let mut entries = Vec::new();
+            loop {
+                let child_id = read_u8(reader)?;
+                if child_id == 0 {
+                    break;
+                }
+                let name = read_string(reader)?;
+                entries.push((name, read_payload(reader, child_id, depth + 1)?));
+            }
+            Tag::Compound(entries)
+        }
+        11 => {
+            let length = read_length(reader)?;
+            let mut values = Vec::with_capacity(length.min(1 << 16));
+            for _ in 0..length {
+                values.push(read_i32(reader)?);
+            }
+            Tag::IntArray(values)
+        }
+        12 => {
+            let length = read_length(reader)?;
+            let mut values = Vec::with_capacity(length.min(1 << 16));
+            for _ in 0..length {
+                values.push(read_i64(reader)?);
+            }
+            Tag::LongArray(values)
+        }
+        _ => return Err(invalid(&format!("Unknown NBT tag id {}", id))),
+    })
+}
+
+// Read an uncompressed named root tag
+pub fn read<R: Read>(reader: &mut R) -> io::Result<(String, Tag)> {
+    let id = read_u8(reader)?;
+    if id == 0 {
+        return Err(invalid("NBT root tag is empty"));
+    }
+    let name = read_string(reader)?;
+    let tag = read_payload(reader, id, 0)?;
+    Ok((name, tag))
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> io::Result<()> {
+    let bytes = encode_modified_utf8(value);
+    let length = u16::try_from(bytes.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "NBT strings can't be longer than 65535 bytes"))?;
+    writer.write_all(&length.to_be_bytes())?;
+    writer.write_all(&bytes)
+}
+
+fn write_length<W: Write>(writer: &mut W, length: usize) -> io::Result<()> {
+    let length = i32::try_from(length)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "NBT lists and arrays can't be this long"))?;
+    writer.write_all(&length.to_be_bytes())
+}
+
+fn write_payload<W: Write>(writer: &mut W, tag: &Tag) -> io::Result<()> {
+    match tag {
+        Tag::Byte(v) => writer.write_all(&v.to_be_bytes()),
+        Tag::Short(v) => writer.write_all(&v.to_be_bytes()),
+        Tag::Int(v) => writer.write_all(&v.to_be_bytes()),
+        Tag::Long(v) => writer.write_all(&v.to_be_bytes()),
+        Tag::Float(v) => writer.write_all(&v.to_be_bytes()),
+        Tag::Double(v) => writer.write_all(&v.to_be_bytes()),
+        Tag::ByteArray(values) => {
+            write_length(writer, values.len())?;
+            let bytes: Vec<u8> = values.iter().map(|b| *b as u8).collect();
+            writer.write_all(&bytes)
+        }
+        Tag::String(v) => write_string(writer, v),
+        Tag::List(element_id, values) => {
+            let element_id = values.first().map(|v| v.id()).unwrap_or(*element_id);
+            writer.write_all(&[element_id])?;
+            write_length(writer, values.len())?;
+            for value in values {
+                write_payload(writer, value)?;
+            }
+            Ok(())
+        }
+        Tag::Compound(entries) => {
+            for (name, value) in entries {
+                writer.write_all(&[value.id()])?;
+                write_string(writer, name)?;
+                write_payload(writer, value)?;
+            }
+            writer.write_all(&[0])
+        }
+        Tag::IntArray(values) => {
+            write_length(writer, values.len())?;
+            for value in values {
+                writer.write_all(&value.to_be_bytes())?;
+            }
+            Ok(())
+        }
+        Tag::LongArray(values) => {
+            write_length(writer, values.len())?;
+            for value in values {
+                writer.write_all(&value.to_be_bytes())?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// Write an uncompressed named root tag
+pub fn write<W: Write>(writer: &mut W, name: &str, tag: &Tag) -> io::Result<()> {
+    writer.write_all(&[tag.id()])?;
+    write_string(writer, name)?;
+    write_payload(writer, tag)
+}
+
+// Read an NBT file such as level.dat or playerdata/<uuid>.dat.
+// These are normally gzipped, but uncompressed files are accepted too.
+pub fn read_file<P: AsRef<Path>>(path: P) -> io::Result<(String, Tag)> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        read(&mut GzDecoder::new(&bytes[..]))
+    } else {
+        read(&mut &bytes[..])
+    }
+}
+
+// Write a gzipped NBT file, the format the game uses for level.dat
+pub fn write_file<P: AsRef<Path>>(path: P, name: &str, tag: &Tag) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    write(&mut encoder, name, tag)?;
+    encoder.finish()?.flush()
+}
@@ -0,0 +1,208 @@
+use crate::console;
+use crate::dotfile;
+use crate::tas::Tas;
+use crate::trash::{self, TrashKind};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Utc};
+use crossterm::style::Color;
+
+// Only the most recent operations are kept
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Operation {
+    // A savestate folder was created in a TAS
+    Create { tas: String, folder: String },
+    // A savestate was loaded into a new attempt world
+    Load { tas: String, savestate: String, world: PathBuf },
+    // A savestate replaced the working world, whose old contents were
+    // moved to the TAS's load backup
+    LoadInPlace { tas: String, savestate: String, world: PathBuf, backup: Option<String> },
+    // A savestate replaced a server's world folders, whose old contents
+    // were moved to the TAS's load backup
+    LoadServer { tas: String, savestate: String, worlds: Vec<PathBuf>, backups: Vec<String> },
+    // A savestate was moved to the trash
+    DeleteSavestate { tas: String, folder: String, trash_id: String },
+    // A world was moved to the trash
+    DeleteWorld { world: PathBuf, trash_id: String },
+}
+
+impl Operation {
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Create { folder, .. } => format!("creation of savestate {}", folder),
+            Operation::Load { savestate, world, .. } => format!("load of savestate {} into world {}", savestate, file_name(world)),
+            Operation::LoadInPlace { savestate, world, .. } => format!("load of savestate {} over world {}", savestate, file_name(world)),
+            Operation::LoadServer { savestate, worlds, .. } => format!("load of savestate {} over server world {}", savestate, file_name(&worlds[0])),
+            Operation::DeleteSavestate { folder, .. } => format!("deletion of savestate {}", folder),
+            Operation::DeleteWorld { world, .. } => format!("deletion of world {}", file_name(world)),
+        }
+    }
+}
+
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogEntry {
+    pub time: DateTime<Utc>,
+    pub operation: Operation,
+}
+
+fn get_log_path() -> PathBuf {
+    dotfile::get_dotfile_path().join("oplog.json")
+}
+
+// The logged operations, oldest first. A log that can't be read is an
+// error, so that recording an operation doesn't overwrite the history.
+pub fn get_entries() -> io::Result<Vec<LogEntry>> {
+    match File::open(get_log_path()) {
+        Ok(file) => serde_json::from_reader(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn save_entries(entries: &[LogEntry]) -> io::Result<()> {
+    let file = File::create(get_log_path())?;
+    serde_json::to_writer(file, entries).map_err(io::Error::other)
+}
+
+// Append an operation to the log. Nothing is recorded while the log
+// can't be read.
+pub fn record(operation: Operation) {
+    let result = get_entries().and_then(|mut entries| {
+        entries.push(LogEntry { time: Utc::now(), operation });
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        save_entries(&entries)
+    });
+    if let Err(e) = result {
+        console::write_line(&Color::Yellow, true, &format!("Not recording the operation for undo, failed to update {}: {}", get_log_path().display(), e));
+    }
+}
+
+pub fn last() -> io::Result<Option<LogEntry>> {
+    Ok(get_entries()?.pop())
+}
+
+fn with_tas<F>(name: &str, current: &mut Tas, f: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Tas) -> Result<(), String>,
+{
+    if current.name == name {
+        return f(current);
+    }
+    let mut tas = dotfile::get_tases().into_iter().find(|t| t.name == name)
+        .ok_or(format!("The TAS {} no longer exists", name))?;
+    f(&mut tas)
+}
+
+// Check that the worlds a load replaced can still be put back, before
+// anything is undone
+fn check_backup(tas_folder: &Path, id: &str) -> Result<(), String> {
+    if trash::get_load_backup_path(tas_folder).join(id).exists() {
+        Ok(())
+    } else {
+        Err("The backup of the worlds it replaced was overwritten by a later load".to_string())
+    }
+}
+
+// Reverse a single operation. The reversal itself is not logged.
+fn reverse(operation: &Operation, current: &mut Tas) -> Result<(), String> {
+    match operation {
+        Operation::Create { tas, folder } => with_tas(tas, current, |tas| {
+            let info = tas.savestates.iter().find(|s| s.folder == *folder).cloned()
+                .ok_or(format!("Savestate {} no longer exists", folder))?;
+            if tas.savestates.iter().any(|s| s.base == Some(info.id)) {
+                return Err("A partial savestate depends on this savestate".to_string());
+            }
+            tas.trash_savestate(&info).map(|_| ()).map_err(|e| e.to_string())
+        }),
+        Operation::Load { tas, savestate, world } => with_tas(tas, current, |tas| {
+            if world.exists() {
+                trash::move_to_trash(world, TrashKind::World).map_err(|e| e.to_string())?;
+            }
+            tas.remove_attempt(savestate);
+            Ok(())
+        }),
+        Operation::LoadInPlace { tas, savestate, world, backup } => {
+            let mut tas_folder = PathBuf::new();
+            with_tas(tas, current, |tas| {
+                if let Some(backup) = backup {
+                    check_backup(&tas.path, backup)?;
+                }
+                if world.exists() {
+                    trash::move_to_trash(world, TrashKind::World).map_err(|e| e.to_string())?;
+                }
+                tas.remove_attempt(savestate);
+                tas_folder = tas.path.clone();
+                Ok(())
+            })?;
+            match backup {
+                Some(backup) => trash::restore_load_backup(&tas_folder, backup, std::slice::from_ref(world)),
+                None => Ok(()),
+            }
+        }
+        Operation::LoadServer { tas, savestate, worlds, backups } => {
+            let mut tas_folder = PathBuf::new();
+            with_tas(tas, current, |tas| {
+                for backup in backups {
+                    check_backup(&tas.path, backup)?;
+                }
+                for world in worlds.iter().filter(|world| world.exists()) {
+                    trash::move_to_trash(world, TrashKind::World).map_err(|e| e.to_string())?;
+                }
+                tas.remove_attempt(savestate);
+                tas_folder = tas.path.clone();
+                Ok(())
+            })?;
+            for backup in backups {
+                trash::restore_load_backup(&tas_folder, backup, worlds)?;
+            }
+            Ok(())
+        }
+        Operation::DeleteSavestate { trash_id, .. } | Operation::DeleteWorld { trash_id, .. } => {
+            trash::restore(trash_id, current).map(|_| ())
+        }
+    }
+}
+
+// Undo the most recent operation, removing it from the log
+pub fn undo(current: &mut Tas) -> Result<Operation, String> {
+    let mut entries = get_entries().map_err(|e| format!("Failed to read {}: {}", get_log_path().display(), e))?;
+    let entry = entries.pop().ok_or("Nothing to undo")?;
+    reverse(&entry.operation, current)?;
+    save_entries(&entries).map_err(|e| e.to_string())?;
+    Ok(entry.operation)
+}
+
+// Ask the user to confirm and undo the most recent operation
+pub fn undo_last(tas: &mut Tas) {
+    let entry = match last() {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            console::write_line(&Color::Yellow, true, "Nothing to undo");
+            return;
+        }
+        Err(e) => {
+            console::write_line(&Color::Red, true, &format!("Failed to read {}: {}", get_log_path().display(), e));
+            return;
+        }
+    };
+    let description = entry.operation.describe();
+    let when = entry.time.format("%H:%M:%S %d/%m/%Y");
+    if !console::confirm(format!("Undo the {} at {}?", description, when), "y") {
+        console::write_line(&Color::Yellow, true, "Undo cancelled");
+        return;
+    }
+    match undo(tas) {
+        Ok(_) => console::write_line(&Color::Green, true, &format!("Undid the {}", description)),
+        Err(e) => console::write_line(&Color::Red, true, &format!("Failed to undo the {}: {}", description, e)),
+    }
+}
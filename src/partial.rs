@@ -0,0 +1,136 @@
+use crate::console;
+use crate::region;
+use std::path::{Component, Path};
+use serde::{Serialize, Deserialize};
+use walkdir::WalkDir;
+use crossterm::style::Color;
+
+pub const OVERWORLD: &str = "overworld";
+pub const NETHER: &str = "DIM-1";
+pub const END: &str = "DIM1";
+
+// Inclusive range of region file coordinates, e.g. r.-1.0.mca is x = -1, z = 0
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RegionRange {
+    pub min_x: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_z: i32,
+}
+
+impl RegionRange {
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        x >= self.min_x && x <= self.max_x && z >= self.min_z && z <= self.max_z
+    }
+}
+
+// Which parts of a world a partial savestate records. level.dat
+// and player data are always included.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PartialSpec {
+    // "overworld", "DIM-1" (nether) or "DIM1" (end)
+    pub dimensions: Vec<String>,
+    // Only region files in this range are recorded, in every chosen dimension
+    pub regions: Option<RegionRange>,
+}
+
+impl PartialSpec {
+    // Check whether a file, relative to the world folder, belongs in the savestate
+    pub fn includes(&self, relative: &Path) -> bool {
+        let first = match relative.components().next() {
+            Some(Component::Normal(first)) => first.to_string_lossy().to_string(),
+            _ => return false,
+        };
+        if first == "level.dat" || first == "session.lock" || first == "playerdata" || first == "players" {
+            return true;
+        }
+
+        let dimension = if first == NETHER || first == END { first.as_str() } else { OVERWORLD };
+        if !self.dimensions.iter().any(|d| d == dimension) {
+            return false;
+        }
+
+        match (&self.regions, region::region_coordinates(relative)) {
+            (Some(range), Some((x, z))) => range.contains(x, z),
+            _ => true,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let dimensions = self.dimensions.join(", ");
+        match &self.regions {
+            Some(r) => format!("{}, regions {},{} to {},{}", dimensions, r.min_x, r.min_z, r.max_x, r.max_z),
+            None => dimensions,
+        }
+    }
+}
+
+// Total size of the files of a world selected by the spec
+pub fn partial_size(world: &Path, spec: &PartialSpec) -> u64 {
+    WalkDir::new(world).min_depth(1).into_iter().flatten()
+        .filter(|entry| entry.file_type().is_file() && spec.includes(entry.path().strip_prefix(world).unwrap()))
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+// Copy only the parts of a world selected by the spec
+pub fn copy_partial(world: &Path, destination: &Path, spec: &PartialSpec) -> std::io::Result<()> {
+    for entry in WalkDir::new(world).min_depth(1).into_iter().flatten() {
+        let relative = entry.path().strip_prefix(world).unwrap();
+        if !entry.file_type().is_file() || !spec.includes(relative) {
+            continue;
+        }
+        let target = destination.join(relative);
+        std::fs::create_dir_all(target.parent().unwrap())?;
+        std::fs::copy(entry.path(), target)?;
+    }
+    Ok(())
+}
+
+fn parse_range(input: &str) -> Option<RegionRange> {
+    let numbers: Vec<i32> = input.split(',').map(|n| n.trim().parse().ok()).collect::<Option<_>>()?;
+    match numbers[..] {
+        [x1, z1, x2, z2] => Some(RegionRange {
+            min_x: x1.min(x2),
+            min_z: z1.min(z2),
+            max_x: x1.max(x2),
+            max_z: z1.max(z2),
+        }),
+        _ => None,
+    }
+}
+
+// Ask the user which dimensions and regions to record
+pub fn prompt_partial_spec() -> PartialSpec {
+    let choices = vec![
+        "Overworld".to_string(),
+        "Nether".to_string(),
+        "End".to_string(),
+        "Overworld and Nether".to_string(),
+        "Nether and End".to_string(),
+    ];
+    let dimensions = match console::present_choices("Choose the dimensions to record".to_string(), choices) {
+        0 => vec![OVERWORLD],
+        1 => vec![NETHER],
+        2 => vec![END],
+        3 => vec![OVERWORLD, NETHER],
+        _ => vec![NETHER, END],
+    };
+
+    let regions = loop {
+        let input = console::get_input("Enter a region range as x1,z1,x2,z2 or leave blank for all regions: ");
+        if input.is_empty() {
+            break None;
+        }
+        match parse_range(&input) {
+            Some(range) => break Some(range),
+            None => console::write_line(&Color::Red, true, "Invalid range, please enter four numbers"),
+        }
+    };
+
+    PartialSpec {
+        dimensions: dimensions.into_iter().map(String::from).collect(),
+        regions,
+    }
+}
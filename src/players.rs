@@ -0,0 +1,195 @@
+use crate::console;
+use crate::level;
+use crate::nbt::{self, Tag};
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crossterm::style::Color;
+
+// The singleplayer player, stored in level.dat under Data.Player.
+// Other players are "<uuid>" for playerdata/<uuid>.dat, or
+// "players/<name>" for the pre-1.7.6 players/<name>.dat files.
+pub const SINGLEPLAYER: &str = "level.dat";
+
+// What to do with player data when a savestate is loaded
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub enum PlayerHandling {
+    #[default]
+    Keep,
+    // Remove all player data, so every player spawns fresh
+    Strip,
+    // Copy one player's data over another's, e.g. a teammate's
+    // playerdata into level.dat to play as them in singleplayer
+    Copy { from: String, to: String },
+    // Exchange the data of two players, e.g. to play as a teammate in
+    // singleplayer and hand their world back to them unchanged
+    Swap { first: String, second: String },
+}
+
+impl PlayerHandling {
+    pub fn describe(&self) -> String {
+        match self {
+            PlayerHandling::Keep => "keep".to_string(),
+            PlayerHandling::Strip => "strip all players".to_string(),
+            PlayerHandling::Copy { from, to } => format!("copy {} over {}", from, to),
+            PlayerHandling::Swap { first, second } => format!("swap {} and {}", first, second),
+        }
+    }
+}
+
+pub struct Player {
+    pub id: String,
+    pub position: Option<[f64; 3]>,
+    pub health: Option<f32>,
+}
+
+impl Player {
+    pub fn describe(&self) -> String {
+        let mut text = self.id.clone();
+        if let Some([x, y, z]) = self.position {
+            text.push_str(&format!(" at {:.1} {:.1} {:.1}", x, y, z));
+        }
+        if let Some(health) = self.health {
+            text.push_str(&format!(", health {}", health));
+        }
+        text
+    }
+}
+
+fn player_file(world: &Path, id: &str) -> PathBuf {
+    match id.strip_prefix("players/") {
+        Some(name) => world.join("players").join(format!("{}.dat", name)),
+        None => world.join("playerdata").join(format!("{}.dat", id)),
+    }
+}
+
+fn dat_stems(folder: &Path) -> Vec<String> {
+    let mut stems = vec![];
+    if let Ok(entries) = std::fs::read_dir(folder) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("dat") {
+                stems.push(path.file_stem().unwrap().to_string_lossy().to_string());
+            }
+        }
+    }
+    stems.sort();
+    stems
+}
+
+// Read a player's NBT data
+pub fn read_player(world: &Path, id: &str) -> io::Result<Tag> {
+    if id == SINGLEPLAYER {
+        let (_, root) = nbt::read_file(world.join("level.dat"))?;
+        return root.get_path("Data.Player").cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "level.dat has no player"));
+    }
+    Ok(nbt::read_file(player_file(world, id))?.1)
+}
+
+// Write a player's NBT data, creating the player if needed
+pub fn write_player(world: &Path, id: &str, player: Tag) -> io::Result<()> {
+    if id == SINGLEPLAYER {
+        return level::edit_level_dat(world, |root| {
+            match root.get_mut("Data") {
+                Some(data) => {
+                    data.insert("Player", player);
+                    Ok(())
+                }
+                None => Err(io::Error::new(io::ErrorKind::InvalidData, "level.dat has no Data tag")),
+            }
+        });
+    }
+    let path = player_file(world, id);
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    nbt::write_file(path, "", &player)
+}
+
+fn describe_player(id: String, tag: &Tag) -> Player {
+    let position = match tag.get("Pos") {
+        Some(Tag::List(_, values)) if values.len() == 3 => {
+            let mut position = [0.0; 3];
+            for (i, value) in values.iter().enumerate() {
+                if let Tag::Double(v) = value {
+                    position[i] = *v;
+                }
+            }
+            Some(position)
+        }
+        _ => None,
+    };
+    let health = match tag.get("Health") {
+        Some(Tag::Float(v)) => Some(*v),
+        Some(Tag::Short(v)) => Some(*v as f32),
+        _ => None,
+    };
+    Player { id, position, health }
+}
+
+// List every player stored in a world or savestate folder
+pub fn list_players(world: &Path) -> Vec<Player> {
+    let mut ids = vec![];
+    if read_player(world, SINGLEPLAYER).is_ok() {
+        ids.push(SINGLEPLAYER.to_string());
+    }
+    ids.extend(dat_stems(&world.join("playerdata")));
+    ids.extend(dat_stems(&world.join("players")).into_iter().map(|name| format!("players/{}", name)));
+
+    ids.into_iter().map(|id| match read_player(world, &id) {
+        Ok(tag) => describe_player(id, &tag),
+        Err(_) => Player { id, position: None, health: None },
+    }).collect()
+}
+
+// Remove all player data from a world
+pub fn strip_players(world: &Path) -> io::Result<()> {
+    for folder in ["playerdata", "players"] {
+        let folder = world.join(folder);
+        if folder.exists() {
+            std::fs::remove_dir_all(&folder)?;
+            std::fs::create_dir(&folder)?;
+        }
+    }
+    level::edit_level_dat(world, |root| {
+        if let Some(data) = root.get_mut("Data") {
+            data.remove("Player");
+        }
+        Ok(())
+    })
+}
+
+// Copy one player's data over another's
+pub fn copy_player(world: &Path, from: &str, to: &str) -> io::Result<()> {
+    let player = read_player(world, from)?;
+    write_player(world, to, player)
+}
+
+// Exchange the data of two players. Both have to exist, and nothing is
+// written unless both could be read.
+pub fn swap_players(world: &Path, first: &str, second: &str) -> io::Result<()> {
+    let first_player = read_player(world, first)?;
+    let second_player = read_player(world, second)?;
+    write_player(world, first, second_player)?;
+    write_player(world, second, first_player)
+}
+
+pub fn apply_handling(world: &Path, handling: &PlayerHandling) -> io::Result<()> {
+    match handling {
+        PlayerHandling::Keep => Ok(()),
+        PlayerHandling::Strip => strip_players(world),
+        PlayerHandling::Copy { from, to } => copy_player(world, from, to),
+        PlayerHandling::Swap { first, second } => swap_players(world, first, second),
+    }
+}
+
+pub fn print_players(world: &Path) {
+    let players = list_players(world);
+    if players.is_empty() {
+        console::write_line(&Color::Yellow, true, "No player data found");
+        return;
+    }
+    console::write_line(&Color::Magenta, true, &format!("{} players:", players.len()));
+    for player in players {
+        console::write_line(&Color::Cyan, false, &format!("  {}", player.describe()));
+    }
+}
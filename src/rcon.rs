@@ -0,0 +1,94 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+// Packet types of the Source RCON protocol used by Minecraft servers
+pub const RESPONSE: i32 = 0;
+pub const COMMAND: i32 = 2;
+pub const LOGIN: i32 = 3;
+
+// Servers reject payloads longer than this
+const MAX_PAYLOAD: usize = 1446;
+// Servers split responses into packets of at most this size
+const MAX_RESPONSE: i32 = 4096;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Packet {
+    pub id: i32,
+    pub kind: i32,
+    pub payload: String,
+}
+
+// Write a packet: length, id and type as little endian i32s,
+// then the payload and two null bytes
+pub fn write_packet<W: Write>(writer: &mut W, packet: &Packet) -> io::Result<()> {
+    let payload = packet.payload.as_bytes();
+    let mut bytes = Vec::with_capacity(payload.len() + 14);
+    bytes.extend_from_slice(&(payload.len() as i32 + 10).to_le_bytes());
+    bytes.extend_from_slice(&packet.id.to_le_bytes());
+    bytes.extend_from_slice(&packet.kind.to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes.extend_from_slice(&[0, 0]);
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+pub fn read_packet<R: Read>(reader: &mut R) -> io::Result<Packet> {
+    let mut int = [0; 4];
+    reader.read_exact(&mut int)?;
+    let length = i32::from_le_bytes(int);
+    if !(10..=MAX_RESPONSE + 10).contains(&length) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid RCON packet length {}", length)));
+    }
+    let mut body = vec![0; length as usize];
+    reader.read_exact(&mut body)?;
+    let id = i32::from_le_bytes(body[0..4].try_into().unwrap());
+    let kind = i32::from_le_bytes(body[4..8].try_into().unwrap());
+    let payload = String::from_utf8_lossy(&body[8..body.len() - 2]).to_string();
+    Ok(Packet { id, kind, payload })
+}
+
+// A logged in connection to a server's RCON port
+pub struct Rcon {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl Rcon {
+    pub fn connect(address: &str, password: &str) -> io::Result<Rcon> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut rcon = Rcon { stream, next_id: 1 };
+
+        let id = rcon.send(LOGIN, password)?;
+        // A failed login is answered with id -1
+        let response = read_packet(&mut rcon.stream)?;
+        if response.id == -1 || response.id != id {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Wrong RCON password"));
+        }
+        Ok(rcon)
+    }
+
+    fn send(&mut self, kind: i32, payload: &str) -> io::Result<i32> {
+        if payload.len() > MAX_PAYLOAD {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "RCON command is too long"));
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        write_packet(&mut self.stream, &Packet { id, kind, payload: payload.to_string() })?;
+        Ok(id)
+    }
+
+    // Run a command and return the server's response
+    pub fn command(&mut self, command: &str) -> io::Result<String> {
+        let id = self.send(COMMAND, command)?;
+        loop {
+            let response = read_packet(&mut self.stream)?;
+            if response.id == id && response.kind == RESPONSE {
+                return Ok(response.payload);
+            }
+        }
+    }
+}
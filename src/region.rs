@@ -0,0 +1,268 @@
+use crate::nbt::{self, Tag};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+// Region files are split into 4KiB sectors. The first sector holds
+// the chunk location table and the second the chunk timestamps.
+pub const SECTOR_SIZE: usize = 4096;
+pub const HEADER_SIZE: usize = SECTOR_SIZE * 2;
+pub const CHUNKS_PER_REGION: usize = 1024;
+
+// Set on the compression byte when the chunk is stored in a
+// separate c.<x>.<z>.mcc file because it is too large
+const EXTERNAL_FLAG: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionFormat {
+    // .mca files, Minecraft 1.2 onwards
+    Anvil,
+    // .mcr files, Beta 1.3 to 1.1
+    McRegion,
+}
+
+impl RegionFormat {
+    pub fn from_path(path: &Path) -> Option<RegionFormat> {
+        match path.extension()?.to_str()? {
+            "mca" => Some(RegionFormat::Anvil),
+            "mcr" => Some(RegionFormat::McRegion),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zlib,
+    Uncompressed,
+    Lz4,
+}
+
+impl Compression {
+    pub fn from_id(id: u8) -> Option<Compression> {
+        match id & !EXTERNAL_FLAG {
+            1 => Some(Compression::Gzip),
+            2 => Some(Compression::Zlib),
+            3 => Some(Compression::Uncompressed),
+            4 => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        match self {
+            Compression::Gzip => 1,
+            Compression::Zlib => 2,
+            Compression::Uncompressed => 3,
+            Compression::Lz4 => 4,
+        }
+    }
+}
+
+// Where a chunk is stored, in sectors from the start of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkLocation {
+    pub offset: u32,
+    pub sectors: u8,
+}
+
+// A chunk's data as stored on disk, before decompression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawChunk {
+    pub compression: Compression,
+    pub external: bool,
+    pub data: Vec<u8>,
+}
+
+pub struct Region {
+    path: Option<PathBuf>,
+    bytes: Vec<u8>,
+    locations: Vec<Option<ChunkLocation>>,
+    timestamps: Vec<u32>,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+// Index of a chunk in the header tables. Coordinates are
+// taken modulo 32 so absolute chunk coordinates work too.
+pub fn chunk_index(x: i32, z: i32) -> usize {
+    (x.rem_euclid(32) + z.rem_euclid(32) * 32) as usize
+}
+
+// Parse the region coordinates from a file name like r.-1.2.mca
+pub fn region_coordinates(path: &Path) -> Option<(i32, i32)> {
+    let name = path.file_name()?.to_str()?;
+    let mut parts = name.split('.');
+    if parts.next()? != "r" {
+        return None;
+    }
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    RegionFormat::from_path(path)?;
+    Some((x, z))
+}
+
+impl Region {
+    // Read a region file from disk. External .mcc chunks
+    // are looked up next to it.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Region> {
+        let path = path.as_ref();
+        let mut region = Region::from_bytes(std::fs::read(path)?)?;
+        region.path = Some(path.to_path_buf());
+        Ok(region)
+    }
+
+    // Parse a region file held in memory. Files without a complete
+    // header, such as those the game leaves empty, hold no chunks.
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<Region> {
+        let mut locations = vec![None; CHUNKS_PER_REGION];
+        let mut timestamps = vec![0; CHUNKS_PER_REGION];
+        if bytes.len() >= HEADER_SIZE {
+            for index in 0..CHUNKS_PER_REGION {
+                let location = read_u32(&bytes, index * 4);
+                let offset = location >> 8;
+                let sectors = (location & 0xff) as u8;
+                if location != 0 && offset >= 2 && sectors > 0 {
+                    locations[index] = Some(ChunkLocation { offset, sectors });
+                }
+                timestamps[index] = read_u32(&bytes, SECTOR_SIZE + index * 4);
+            }
+        }
+
+        Ok(Region { path: None, bytes, locations, timestamps })
+    }
+
+    pub fn location(&self, x: i32, z: i32) -> Option<ChunkLocation> {
+        self.locations[chunk_index(x, z)]
+    }
+
+    // Last save time of a chunk in seconds since the epoch, 0 if never saved
+    pub fn timestamp(&self, x: i32, z: i32) -> u32 {
+        self.timestamps[chunk_index(x, z)]
+    }
+
+    // Local (0-31) coordinates of every chunk present in the region
+    pub fn chunks(&self) -> Vec<(i32, i32)> {
+        (0..CHUNKS_PER_REGION)
+            .filter(|i| self.locations[*i].is_some())
+            .map(|i| ((i % 32) as i32, (i / 32) as i32))
+            .collect()
+    }
+
+    // Read a chunk's data without decompressing it
+    pub fn raw_chunk(&self, x: i32, z: i32) -> io::Result<Option<RawChunk>> {
+        let location = match self.location(x, z) {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+        let start = location.offset as usize * SECTOR_SIZE;
+        if start + 5 > self.bytes.len() {
+            return Err(invalid(format!("Chunk {}, {} starts past the end of the region file", x, z)));
+        }
+        let length = read_u32(&self.bytes, start) as usize;
+        if length == 0 {
+            return Err(invalid(format!("Chunk {}, {} has zero length", x, z)));
+        }
+        let compression_id = self.bytes[start + 4];
+        let compression = Compression::from_id(compression_id)
+            .ok_or_else(|| invalid(format!("Unknown compression type {} for chunk {}, {}", compression_id, x, z)))?;
+        let external = compression_id & EXTERNAL_FLAG != 0;
+
+        let data = if external {
+            self.read_external(x, z)?
+        } else {
+            let end = start + 4 + length;
+            if end > self.bytes.len() {
+                return Err(invalid(format!("Chunk {}, {} runs past the end of the region file", x, z)));
+            }
+            self.bytes[start + 5..end].to_vec()
+        };
+
+        Ok(Some(RawChunk { compression, external, data }))
+    }
+
+    fn read_external(&self, x: i32, z: i32) -> io::Result<Vec<u8>> {
+        let path = self.path.as_ref()
+            .ok_or_else(|| invalid(format!("Chunk {}, {} is stored externally but the region has no path", x, z)))?;
+        let (region_x, region_z) = region_coordinates(path)
+            .ok_or_else(|| invalid(format!("Cannot find external chunk for region {}", path.display())))?;
+        let chunk_x = region_x * 32 + x.rem_euclid(32);
+        let chunk_z = region_z * 32 + z.rem_euclid(32);
+        std::fs::read(path.with_file_name(format!("c.{}.{}.mcc", chunk_x, chunk_z)))
+    }
+
+    // Read and decompress a chunk
+    pub fn read_chunk(&self, x: i32, z: i32) -> io::Result<Option<Vec<u8>>> {
+        match self.raw_chunk(x, z)? {
+            Some(raw) => Ok(Some(decompress(raw.compression, &raw.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Read, decompress and parse a chunk's NBT
+    pub fn read_chunk_nbt(&self, x: i32, z: i32) -> io::Result<Option<Tag>> {
+        match self.read_chunk(x, z)? {
+            Some(data) => Ok(Some(nbt::read(&mut &data[..])?.1)),
+            None => Ok(None),
+        }
+    }
+}
+
+pub fn decompress(compression: Compression, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match compression {
+        Compression::Gzip => { GzDecoder::new(data).read_to_end(&mut out)?; }
+        Compression::Zlib => { ZlibDecoder::new(data).read_to_end(&mut out)?; }
+        Compression::Uncompressed => out.extend_from_slice(data),
+        Compression::Lz4 => out = decompress_lz4_blocks(data)?,
+    }
+    Ok(out)
+}
+
+// The game writes LZ4 chunks with lz4-java's block stream: a sequence
+// of blocks, each with an "LZ4Block" header, ended by an empty block.
+fn decompress_lz4_blocks(mut data: &[u8]) -> io::Result<Vec<u8>> {
+    const MAGIC: &[u8] = b"LZ4Block";
+    const BLOCK_HEADER_SIZE: usize = MAGIC.len() + 13;
+    const METHOD_RAW: u8 = 0x10;
+    const METHOD_LZ4: u8 = 0x20;
+
+    let mut out = Vec::new();
+    loop {
+        if data.is_empty() {
+            return Ok(out);
+        }
+        if data.len() < BLOCK_HEADER_SIZE || !data.starts_with(MAGIC) {
+            return Err(invalid("Malformed LZ4 block header".to_string()));
+        }
+        let token = data[MAGIC.len()];
+        let compressed_length = u32::from_le_bytes(data[9..13].try_into().unwrap()) as usize;
+        let decompressed_length = u32::from_le_bytes(data[13..17].try_into().unwrap()) as usize;
+        data = &data[BLOCK_HEADER_SIZE..];
+        if decompressed_length == 0 {
+            return Ok(out);
+        }
+        if compressed_length > data.len() {
+            return Err(invalid("LZ4 block runs past the end of the chunk".to_string()));
+        }
+
+        let block = &data[..compressed_length];
+        match token & 0xf0 {
+            METHOD_RAW => out.extend_from_slice(block),
+            METHOD_LZ4 => {
+                let decompressed = lz4_flex::block::decompress(block, decompressed_length)
+                    .map_err(|e| invalid(format!("Invalid LZ4 block: {}", e)))?;
+                out.extend_from_slice(&decompressed);
+            }
+            method => return Err(invalid(format!("Unknown LZ4 block method {:#x}", method))),
+        }
+        data = &data[compressed_length..];
+    }
+}
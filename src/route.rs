@@ -0,0 +1,193 @@
+use crate::console;
+use crate::dotfile;
+use crate::stats;
+use crate::tas::{Savestate, Tas};
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+use crossterm::style::Color;
+
+// A named step of a route and the savestate it starts from
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RouteSegment {
+    pub name: String,
+    // Id of the canonical savestate for this segment
+    #[serde(default)]
+    pub savestate: Option<usize>,
+}
+
+pub fn get_canonical<'a>(tas: &'a Tas, segment: &RouteSegment) -> Option<&'a Savestate> {
+    segment.savestate.and_then(|id| tas.get_savestate_by_id(id))
+}
+
+pub fn describe_segment(tas: &Tas, segment: &RouteSegment) -> String {
+    let savestate = match (segment.savestate, get_canonical(tas, segment)) {
+        (_, Some(info)) => format!("#{} {}", info.id, info.nickname),
+        (Some(id), None) => format!("#{} (missing)", id),
+        (None, None) => "no savestate".to_string(),
+    };
+    format!("{}: {}", segment.name, savestate)
+}
+
+fn get_last_loaded(tas: &Tas) -> Option<String> {
+    Some(stats::get_attempts(&tas.path).ok()?.pop()?.savestate)
+}
+
+// The segment a savestate belongs to: the one whose canonical savestate
+// is the savestate itself or the closest savestate it was created from
+pub fn get_segment_of(tas: &Tas, folder: &str) -> Option<usize> {
+    let info = tas.savestates.iter().find(|s| s.folder == folder)?;
+    tas.get_ancestry(info).into_iter().rev().find_map(|ancestor| {
+        tas.route.iter().position(|segment| segment.savestate == Some(ancestor.id))
+    })
+}
+
+// The segment of the savestate loaded last, if any
+pub fn current_segment(tas: &Tas) -> Option<usize> {
+    get_segment_of(tas, &get_last_loaded(tas)?)
+}
+
+// The canonical savestate of the segment after the one a savestate
+// belongs to, or of the first segment if nothing was loaded yet
+pub fn get_next_savestate(tas: &Tas, last_loaded: Option<&str>) -> Result<PathBuf, String> {
+    if tas.route.is_empty() {
+        return Err("This TAS has no route, promote a savestate to start one".to_string());
+    }
+    let next = match last_loaded {
+        Some(folder) => get_segment_of(tas, folder)
+            .ok_or(format!("The last loaded savestate {} isn't part of the route, load a segment first", folder))? + 1,
+        None => 0,
+    };
+    let segment = tas.route.get(next).ok_or("The last loaded segment is the end of the route")?;
+    let info = get_canonical(tas, segment).ok_or(format!("Segment {} has no savestate", segment.name))?;
+    Ok(tas.path.join("savestates").join(&info.folder))
+}
+
+// The canonical savestate of the segment after the one loaded last
+pub fn next_savestate(tas: &Tas) -> Result<PathBuf, String> {
+    get_next_savestate(tas, get_last_loaded(tas).as_deref())
+}
+
+// Check a name for a segment, other than the segment at the given
+// position if it is being renamed. Returns the name without spaces
+// around it.
+pub fn check_segment_name(tas: &Tas, name: &str, renamed: Option<usize>) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Segment names can't be empty".to_string());
+    }
+    let taken = tas.route.iter().enumerate().any(|(i, segment)| Some(i) != renamed && segment.name == name);
+    if taken {
+        return Err(format!("There already is a segment named {}", name));
+    }
+    Ok(name.to_string())
+}
+
+// Make a savestate the canonical one for a segment, adding the
+// segment to the end of the route if there is none with that name
+pub fn promote(tas: &mut Tas, id: usize, segment_name: &str) -> Result<(), String> {
+    let segment_name = segment_name.trim();
+    match tas.route.iter_mut().find(|segment| segment.name == segment_name) {
+        Some(segment) => segment.savestate = Some(id),
+        None => {
+            let name = check_segment_name(tas, segment_name, None)?;
+            tas.route.push(RouteSegment { name, savestate: Some(id) });
+        }
+    }
+    dotfile::update_tas(tas);
+    Ok(())
+}
+
+pub fn print_route(tas: &Tas) {
+    if tas.route.is_empty() {
+        console::write_line(&Color::Yellow, true, "This TAS has no route yet");
+        return;
+    }
+    let current = current_segment(tas);
+    console::write_line(&Color::Magenta, true, &format!("Route of {}:", tas.name));
+    for (i, segment) in tas.route.iter().enumerate() {
+        let attempts = get_canonical(tas, segment)
+            .and_then(|info| tas.attempts.get(&info.folder))
+            .map_or(0, |attempt| attempt + 1);
+        let marker = if current == Some(i) { ">" } else { " " };
+        console::write_line(&Color::Cyan, false, &format!("{} {}. {} ({} attempts)", marker, i + 1, describe_segment(tas, segment), attempts));
+    }
+}
+
+fn choose_segment(tas: &Tas, prompt: &str) -> Option<usize> {
+    if tas.route.is_empty() {
+        console::write_line(&Color::Yellow, true, "This TAS has no route yet");
+        return None;
+    }
+    let names: Vec<String> = tas.route.iter().map(|segment| describe_segment(tas, segment)).collect();
+    Some(console::present_choices(prompt.to_string(), names))
+}
+
+fn promote_menu(tas: &mut Tas) {
+    let Some(savestate) = tas.choose_savestate("Choose a savestate to promote") else {
+        return;
+    };
+    let Some(id) = tas.get_savestate_info(&savestate).map(|info| info.id) else {
+        return;
+    };
+    let mut names: Vec<String> = tas.route.iter().map(|segment| describe_segment(tas, segment)).collect();
+    names.push("A new segment at the end of the route".to_string());
+    let choice = console::present_choices("Choose the segment it starts".to_string(), names);
+    let name = match tas.route.get(choice) {
+        Some(segment) => segment.name.clone(),
+        None => match check_segment_name(tas, &console::get_input("Enter a name for the new segment: "), None) {
+            Ok(name) => name,
+            Err(e) => {
+                console::write_line(&Color::Red, true, &e);
+                return;
+            }
+        },
+    };
+    match promote(tas, id, &name) {
+        Ok(()) => console::write_line(&Color::Green, true, &format!("Savestate #{} is now the start of {}", id, name)),
+        Err(e) => console::write_line(&Color::Red, true, &e),
+    }
+}
+
+// Let the user view and edit the route. Returns a savestate
+// to load if the user chose to load the next segment.
+pub fn route_menu(tas: &mut Tas) -> Option<PathBuf> {
+    let choices = vec![
+        "Print the route".to_string(),
+        "Load the next segment".to_string(),
+        "Promote a savestate to be the start of a segment".to_string(),
+        "Rename a segment".to_string(),
+        "Move a segment up".to_string(),
+        "Remove a segment".to_string(),
+    ];
+    match console::present_choices("Choose a route action".to_string(), choices) {
+        0 => print_route(tas),
+        1 => match next_savestate(tas) {
+            Ok(savestate) => return Some(savestate),
+            Err(e) => console::write_line(&Color::Red, true, &e),
+        },
+        2 => promote_menu(tas),
+        3 => {
+            if let Some(i) = choose_segment(tas, "Choose a segment to rename") {
+                match check_segment_name(tas, &console::get_input("Enter the new name: "), Some(i)) {
+                    Ok(name) => tas.route[i].name = name,
+                    Err(e) => console::write_line(&Color::Red, true, &e),
+                }
+            }
+        }
+        4 => {
+            if let Some(i) = choose_segment(tas, "Choose a segment to move up") {
+                if i > 0 {
+                    tas.route.swap(i, i - 1);
+                }
+            }
+        }
+        _ => {
+            if let Some(i) = choose_segment(tas, "Choose a segment to remove") {
+                let segment = tas.route.remove(i);
+                console::write_line(&Color::Green, true, &format!("Removed segment {}, its savestate is kept", segment.name));
+            }
+        }
+    }
+    dotfile::update_tas(tas);
+    None
+}
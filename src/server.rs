@@ -0,0 +1,173 @@
+use crate::console;
+use crate::launch;
+use crate::rcon::Rcon;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+use crossterm::style::Color;
+
+// Whether a TAS saves worlds from a game client's saves folder or a server
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Target {
+    #[default]
+    Client,
+    Server,
+}
+
+// How long to wait for a stopped server to shut down
+const STOP_TIMEOUT_SECS: u64 = 60;
+
+// Bukkit, Spigot and Paper keep the nether and end in folders next to
+// the main world. In a savestate they are stored in these subfolders.
+pub const SPLIT_DIMENSIONS: [&str; 2] = ["_nether", "_the_end"];
+
+// Read a value from server.properties
+pub fn read_property(server_folder: &Path, key: &str) -> Option<String> {
+    let file = File::open(server_folder.join("server.properties")).ok()?;
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once('=') {
+            if k.trim() == key {
+                return Some(v.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+// The main world folder of a server, from level-name in server.properties
+pub fn get_world(server_folder: &Path) -> PathBuf {
+    let level_name = read_property(server_folder, "level-name")
+        .filter(|name| !name.is_empty())
+        .unwrap_or("world".to_string());
+    server_folder.join(level_name)
+}
+
+// The folder a split dimension uses next to a main world, e.g. world_nether
+pub fn split_dimension_folder(world: &Path, suffix: &str) -> PathBuf {
+    let name = world.file_name().unwrap().to_string_lossy();
+    world.with_file_name(format!("{}{}", name, suffix))
+}
+
+pub fn is_server_folder(path: &Path) -> bool {
+    path.join("server.properties").is_file()
+}
+
+// Ask the user for the path to a server folder
+pub fn choose_server_folder() -> PathBuf {
+    loop {
+        let path = PathBuf::from(console::get_input("Please enter the path to your server folder: "));
+        if is_server_folder(&path) {
+            return path;
+        }
+        console::write_line(&Color::Red, true, "No server.properties found in that folder, please try again");
+    }
+}
+
+// The RCON address and password from server.properties, if RCON is enabled
+pub fn rcon_settings(server_folder: &Path) -> Option<(String, String)> {
+    if read_property(server_folder, "enable-rcon")? != "true" {
+        return None;
+    }
+    let port = read_property(server_folder, "rcon.port")
+        .filter(|port| !port.is_empty())
+        .unwrap_or("25575".to_string());
+    let password = read_property(server_folder, "rcon.password")?;
+    Some((format!("127.0.0.1:{}", port), password))
+}
+
+// Run f with automatic saving turned off and every chunk flushed to disk,
+// so the world folders don't change while they are copied. If the server
+// can't be reached over RCON, f is run anyway after a warning.
+pub fn with_saving_paused<T, F: FnOnce() -> T>(server_folder: &Path, f: F) -> T {
+    let mut rcon = match rcon_settings(server_folder) {
+        Some((address, password)) => match Rcon::connect(&address, &password) {
+            Ok(rcon) => Some(rcon),
+            Err(e) => {
+                console::write_line(&Color::Yellow, true, &format!("Could not connect to the server over RCON, saving anyway: {}", e));
+                None
+            }
+        },
+        None => {
+            console::write_line(&Color::Yellow, true, "RCON is not enabled in server.properties, the savestate may be inconsistent if the server is running");
+            None
+        }
+    };
+    if let Some(rcon) = &mut rcon {
+        if let Err(e) = rcon.command("save-off").and_then(|_| rcon.command("save-all flush")) {
+            console::write_line(&Color::Yellow, true, &format!("Failed to flush the world to disk, saving anyway: {}", e));
+        }
+    }
+
+    let result = f();
+
+    if let Some(rcon) = &mut rcon {
+        if let Err(e) = rcon.command("save-on") {
+            console::write_line(&Color::Red, true, &format!("Failed to turn saving back on, run save-on in the server console: {}", e));
+        }
+    }
+    result
+}
+
+// Whether anything answers on the server's game or RCON port
+pub fn is_running(server_folder: &Path) -> bool {
+    let ip = read_property(server_folder, "server-ip")
+        .filter(|ip| !ip.is_empty())
+        .unwrap_or("127.0.0.1".to_string());
+    let port = read_property(server_folder, "server-port")
+        .filter(|port| !port.is_empty())
+        .unwrap_or("25565".to_string());
+    let mut addresses = vec![format!("{}:{}", ip, port)];
+    if let Some((address, _)) = rcon_settings(server_folder) {
+        addresses.push(address);
+    }
+    addresses.iter()
+        .filter_map(|address| address.to_socket_addrs().ok()?.next())
+        .any(|address| TcpStream::connect_timeout(&address, Duration::from_secs(1)).is_ok())
+}
+
+// Stop the server over RCON and wait for it to shut down.
+// A server that isn't running counts as stopped.
+pub fn stop_server(server_folder: &Path) -> Result<(), String> {
+    let (address, password) = rcon_settings(server_folder).ok_or("RCON is not enabled in server.properties")?;
+    let mut rcon = match Rcon::connect(&address, &password) {
+        Ok(rcon) => rcon,
+        Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => return Ok(()),
+        Err(e) => return Err(e.to_string()),
+    };
+    // The server may close the connection before answering
+    let _ = rcon.command("stop");
+    drop(rcon);
+
+    console::write_line(&Color::Cyan, false, "Waiting for the server to stop...");
+    for _ in 0..STOP_TIMEOUT_SECS {
+        std::thread::sleep(Duration::from_secs(1));
+        if TcpStream::connect(&address).is_err() {
+            // RCON closes last, give the process a moment to exit
+            std::thread::sleep(Duration::from_secs(1));
+            return Ok(());
+        }
+    }
+    Err(format!("The server did not stop within {} seconds", STOP_TIMEOUT_SECS))
+}
+
+// Start the server in the background by running a command in its folder.
+// Like launch commands, it is run directly rather than through a shell.
+pub fn start_server(server_folder: &Path, command: &str) -> io::Result<()> {
+    let arguments = launch::split_arguments(command).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    Command::new(&arguments[0])
+        .args(&arguments[1..])
+        .current_dir(server_folder)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use crate::autosave;
+use crate::console;
+use crate::dotfile;
+use crate::launch;
+use crate::hooks;
+use crate::level::LoadPatch;
+use crate::players::{self, PlayerHandling};
+use crate::server::Target;
+use crate::tas::{self, Tas};
+use crate::worlds;
+use crate::usage::{self, QuotaMode};
+use crossterm::style::Color;
+
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+fn describe_quota(tas: &Tas) -> String {
+    match tas.quota {
+        Some(quota) => format!("{} ({})", usage::format_size(quota), match tas.quota_mode {
+            QuotaMode::Warn => "warn",
+            QuotaMode::Refuse => "refuse new savestates",
+        }),
+        None => "none".to_string(),
+    }
+}
+
+fn edit_working_world(tas: &mut Tas) {
+    let choices = vec![
+        "Create a new world for every attempt".to_string(),
+        "Replace a world from the saves folder on every load".to_string(),
+        "Replace a new world with a name of your choice on every load".to_string(),
+    ];
+    tas.working_world = match console::present_choices("Choose how savestates are loaded".to_string(), choices) {
+        0 => None,
+        1 => {
+            let world = worlds::choose_world(tas.minecraft_folder.clone());
+            Some(world.file_name().unwrap().to_string_lossy().to_string())
+        }
+        _ => {
+            let name = console::get_input("Enter a name for the working world: ");
+            Some(tas::sanitize_folder_name(&name)).filter(|name| !name.is_empty())
+        }
+    };
+    match &tas.working_world {
+        Some(world) => console::write_line(&Color::Green, true, &format!("Savestates will be loaded over {}, the world replaced by the latest load is kept as the TAS's load backup", world)),
+        None => console::write_line(&Color::Green, true, "Savestates will be loaded into a new world for every attempt"),
+    }
+}
+
+fn edit_level_name_template(tas: &mut Tas) {
+    console::write_line(&Color::Cyan, false, "Available placeholders: {tas}, {attempt}, {nickname}, {id}, {savestate}");
+    let input = console::get_input(&format!("Enter a level name template, 'default' for \"{}\", or leave blank to keep world names: ", tas::DEFAULT_LEVEL_NAME_TEMPLATE));
+    tas.level_name_template = match input.as_str() {
+        "" => None,
+        "default" => Some(tas::DEFAULT_LEVEL_NAME_TEMPLATE.to_string()),
+        _ => Some(input),
+    };
+    match &tas.level_name_template {
+        Some(template) => console::write_line(&Color::Green, true, &format!("Loaded worlds will be named \"{}\"", template)),
+        None => console::write_line(&Color::Green, true, "Loaded worlds will keep their names"),
+    }
+}
+
+fn prompt_patch() -> LoadPatch {
+    console::write_line(&Color::Cyan, false, "Leave any value blank to keep it unchanged");
+    let mut patch = LoadPatch {
+        day_time: console::get_optional_input("Time of day in ticks (0 sunrise, 6000 noon, 13000 night): "),
+        clear_weather: console::get_input("Clear the weather? (y/n): ").to_lowercase() == "y",
+        health: console::get_optional_input("Player health (20 is full): "),
+        food_level: console::get_optional_input("Player hunger (20 is full): "),
+        ..Default::default()
+    };
+    let x: Option<f64> = console::get_optional_input("Teleport the player to x: ");
+    if let Some(x) = x {
+        let y = console::get_optional_input("y: ").unwrap_or(64.0);
+        let z = console::get_optional_input("z: ").unwrap_or(0.0);
+        patch.teleport = Some([x, y, z]);
+    }
+    loop {
+        let rule = console::get_input("Gamerule to set, e.g. doDaylightCycle (blank to finish): ");
+        if rule.is_empty() {
+            break;
+        }
+        let value = console::get_input(&format!("Value for {}: ", rule));
+        patch.gamerules.insert(rule, value);
+    }
+    patch
+}
+
+fn edit_patch_profiles(tas: &mut Tas) {
+    let mut choices = vec![
+        "Don't patch loaded worlds".to_string(),
+        "Create a new patch profile".to_string(),
+    ];
+    let names: Vec<String> = tas.patch_profiles.keys().cloned().collect();
+    for name in &names {
+        choices.push(format!("Use {}: {}", name, tas.patch_profiles[name].describe()));
+    }
+    if !names.is_empty() {
+        choices.push("Delete a patch profile".to_string());
+    }
+
+    let choice = console::present_choices("Choose a patch profile".to_string(), choices);
+    match choice {
+        0 => tas.active_patch_profile = None,
+        1 => {
+            let name = console::get_input("Enter a name for the patch profile: ");
+            let patch = prompt_patch();
+            tas.patch_profiles.insert(name.clone(), patch);
+            tas.active_patch_profile = Some(name);
+        }
+        i if i - 2 < names.len() => tas.active_patch_profile = Some(names[i - 2].clone()),
+        _ => {
+            let choice = console::present_choices("Choose a patch profile to delete".to_string(), names.clone());
+            tas.patch_profiles.remove(&names[choice]);
+            if tas.active_patch_profile.as_ref() == Some(&names[choice]) {
+                tas.active_patch_profile = None;
+            }
+        }
+    }
+    match &tas.active_patch_profile {
+        Some(name) => console::write_line(&Color::Green, true, &format!("Loaded worlds will be patched with {}", name)),
+        None => console::write_line(&Color::Green, true, "Loaded worlds will not be patched"),
+    }
+}
+
+fn choose_player(prompt: &str, players: &[String]) -> String {
+    let mut choices = players.to_vec();
+    choices.push("Enter a player id manually".to_string());
+    let choice = console::present_choices(prompt.to_string(), choices);
+    if choice < players.len() {
+        players[choice].clone()
+    } else {
+        console::get_input("Enter level.dat, a player UUID, or players/<name>: ")
+    }
+}
+
+fn edit_player_handling(tas: &mut Tas) {
+    let choices = vec![
+        "Keep player data as it was saved".to_string(),
+        "Strip all player data".to_string(),
+        "Copy one player's data over another's".to_string(),
+        "Swap the data of two players".to_string(),
+    ];
+    let choice = console::present_choices("Choose what happens to player data on load".to_string(), choices);
+    // Offer the players found in any savestate of this TAS
+    let mut known: Vec<String> = tas.savestates.iter().flat_map(|s| s.players.clone()).collect();
+    known.sort();
+    known.dedup();
+    let mut with_singleplayer = known.clone();
+    if !with_singleplayer.iter().any(|p| p == players::SINGLEPLAYER) {
+        with_singleplayer.insert(0, players::SINGLEPLAYER.to_string());
+    }
+    tas.player_handling = match choice {
+        0 => PlayerHandling::Keep,
+        1 => PlayerHandling::Strip,
+        2 => {
+            let from = choose_player("Choose the player to copy from", &known);
+            let to = choose_player("Choose the player to overwrite", &with_singleplayer);
+            PlayerHandling::Copy { from, to }
+        }
+        _ => {
+            let first = choose_player("Choose the first player", &with_singleplayer);
+            let second = choose_player("Choose the player to swap with", &with_singleplayer);
+            PlayerHandling::Swap { first, second }
+        }
+    };
+    console::write_line(&Color::Green, true, &format!("Player data on load: {}", tas.player_handling.describe()));
+}
+
+fn edit_server_start_command(tas: &mut Tas) {
+    if tas.target != Target::Server {
+        console::write_line(&Color::Red, true, "Only server TASes can restart a server");
+        return;
+    }
+    console::write_line(&Color::Cyan, false, "The server is stopped over RCON before a load, then started with this command from the server folder");
+    console::write_line(&Color::Cyan, false, "The command is run directly, not through a shell, so run a script to use pipes or variables");
+    let command = console::get_input("Enter the command that starts the server, e.g. java -jar server.jar nogui (blank to turn off): ");
+    tas.server_start_command = Some(command).filter(|command| !command.is_empty());
+    match &tas.server_start_command {
+        Some(command) => console::write_line(&Color::Green, true, &format!("The server will be restarted around loads with {}", command)),
+        None => console::write_line(&Color::Green, true, "The server will not be restarted around loads"),
+    }
+}
+
+fn edit_hooks(tas: &mut Tas) {
+    let choices: Vec<String> = hooks::EVENTS.iter()
+        .map(|event| format!("{}: {}", event.name(), tas.hooks.get(*event).clone().unwrap_or("none".to_string())))
+        .collect();
+    let event = hooks::EVENTS[console::present_choices("Choose a hook to change".to_string(), choices)];
+    console::write_line(&Color::Cyan, false, "Hooks get SAVESTATES_EVENT, SAVESTATES_TAS, SAVESTATES_SAVESTATE and SAVESTATES_WORLD");
+    if event.name().starts_with("pre_") {
+        console::write_line(&Color::Cyan, false, "If the command fails, the operation is cancelled");
+    }
+    let command = console::get_input(&format!("Enter the command to run on {} (blank to remove): ", event.name()));
+    *tas.hooks.get_mut(event) = Some(command).filter(|command| !command.is_empty());
+    match tas.hooks.get(event) {
+        Some(command) => console::write_line(&Color::Green, true, &format!("{} will run {}", event.name(), command)),
+        None => console::write_line(&Color::Green, true, &format!("{} hook removed", event.name())),
+    }
+}
+
+fn edit_autosave(tas: &mut Tas) {
+    tas.autosave_minutes = console::get_optional_input("Enter the minutes between snapshots (blank to turn off): ")
+        .filter(|minutes: &u64| *minutes > 0);
+    if tas.autosave_minutes.is_some() {
+        tas.autosave_keep = console::get_int_input("Enter how many snapshots to keep: ", 1, 100) as usize;
+    }
+    console::write_line(&Color::Green, true, &format!("Automatic snapshots: {}", autosave::describe_setting(tas)));
+}
+
+fn edit_quota(tas: &mut Tas) {
+    loop {
+        let input = console::get_input("Enter a quota in GB, or leave blank for no quota: ");
+        if input.is_empty() {
+            tas.quota = None;
+            break;
+        }
+        match input.parse::<f64>() {
+            Ok(gb) if gb > 0.0 => {
+                tas.quota = Some((gb * 1_000_000_000.0) as u64);
+                break;
+            }
+            _ => console::write_line(&Color::Red, true, "Please enter a positive number"),
+        }
+    }
+    if tas.quota.is_some() {
+        let choices = vec![
+            "Warn when the quota is exceeded".to_string(),
+            "Refuse new savestates when the quota is exceeded".to_string(),
+        ];
+        tas.quota_mode = match console::present_choices("Choose what happens when the quota is exceeded".to_string(), choices) {
+            0 => QuotaMode::Warn,
+            _ => QuotaMode::Refuse,
+        };
+    }
+    console::write_line(&Color::Green, true, &format!("Quota set to {}", describe_quota(tas)));
+}
+
+// Let the user view and change the settings stored in a TAS file
+pub fn edit_settings(tas: &mut Tas) {
+    loop {
+        let choices = vec![
+            format!("Verify savestates before loading: {}", on_off(tas.verify_on_load)),
+            format!("Storage quota: {}", describe_quota(tas)),
+            format!("Load into working world: {}", tas.working_world.clone().unwrap_or("off".to_string())),
+            format!("Loaded world name: {}", tas.level_name_template.clone().unwrap_or("unchanged".to_string())),
+            format!("Patch profile applied on load: {}", tas.active_patch_profile.clone().unwrap_or("none".to_string())),
+            format!("Player data on load: {}", tas.player_handling.describe()),
+            format!("Restart server on load: {}", tas.server_start_command.clone().unwrap_or("off".to_string())),
+            format!("Launch command for this .minecraft folder: {}", match launch::get_launch_command(&tas.minecraft_folder) {
+                Ok(command) => command.unwrap_or("off".to_string()),
+                Err(e) => format!("launch.json can't be read ({})", e),
+            }),
+            format!("Hooks: {} set", tas.hooks.count()),
+            format!("Automatic snapshots of the loaded world: {}", autosave::describe_setting(tas)),
+            "Back".to_string(),
+        ];
+        let choice = console::present_choices("Choose a setting to change".to_string(), choices);
+        match choice {
+            0 => {
+                tas.verify_on_load = !tas.verify_on_load;
+                console::write_line(&Color::Green, true, &format!("Verification before loading turned {}", on_off(tas.verify_on_load)));
+            }
+            1 => edit_quota(tas),
+            2 => edit_working_world(tas),
+            3 => edit_level_name_template(tas),
+            4 => edit_patch_profiles(tas),
+            5 => edit_player_handling(tas),
+            6 => edit_server_start_command(tas),
+            7 => {
+                if tas.target == Target::Server {
+                    console::write_line(&Color::Red, true, "Server TASes are started with the server start command");
+                } else {
+                    launch::edit_launch_command(&tas.minecraft_folder);
+                }
+            }
+            8 => edit_hooks(tas),
+            9 => edit_autosave(tas),
+            _ => break,
+        }
+        dotfile::update_tas(tas);
+    }
+}
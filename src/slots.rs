@@ -0,0 +1,108 @@
+use crate::console;
+use crate::dotfile;
+use crate::tas::{Savestate, Tas};
+use std::path::PathBuf;
+use crossterm::style::Color;
+
+// Slots are numbered 0 to 9, like the hotkeys of an emulator
+pub const SLOT_COUNT: u8 = 10;
+
+pub fn check_slot(slot: u8) -> Result<(), String> {
+    if slot < SLOT_COUNT {
+        Ok(())
+    } else {
+        Err(format!("Slot {} doesn't exist, slots are numbered 0 to {}", slot, SLOT_COUNT - 1))
+    }
+}
+
+// The savestate a slot points at: the latest one saved to it that
+// hasn't been deleted since
+pub fn get_current(tas: &Tas, slot: u8) -> Option<&Savestate> {
+    tas.slots.get(&slot)?.iter().rev().find_map(|id| tas.get_savestate_by_id(*id))
+}
+
+pub fn get_slot_path(tas: &Tas, slot: u8) -> Result<PathBuf, String> {
+    check_slot(slot)?;
+    let info = get_current(tas, slot).ok_or(format!("Slot {} is empty", slot))?;
+    Ok(tas.path.join("savestates").join(&info.folder))
+}
+
+// Savestate the latest world and point the slot at it. The savestates
+// the slot pointed at before are kept as its history.
+pub fn save_to_slot(tas: &mut Tas, slot: u8) -> Result<PathBuf, String> {
+    check_slot(slot)?;
+    let savestate = tas.save_latest_world(format!("slot {}", slot)).ok_or("The savestate was not created")?;
+    let id = tas.get_savestate_info(&savestate).unwrap().id;
+    tas.slots.entry(slot).or_default().push(id);
+    dotfile::update_tas(tas);
+    Ok(savestate)
+}
+
+// Point the slot back at the savestate it held before the current one
+pub fn revert_slot(tas: &mut Tas, slot: u8) -> Result<Option<&Savestate>, String> {
+    check_slot(slot)?;
+    let current = get_current(tas, slot).map(|info| info.id).ok_or(format!("Slot {} is empty", slot))?;
+    let history = tas.slots.get_mut(&slot).unwrap();
+    let position = history.iter().rposition(|id| *id == current).unwrap();
+    history.truncate(position);
+    if history.is_empty() {
+        tas.slots.remove(&slot);
+    }
+    dotfile::update_tas(tas);
+    Ok(get_current(tas, slot))
+}
+
+pub fn describe_slot(tas: &Tas, slot: u8) -> String {
+    let history = tas.slots.get(&slot).map_or(0, |ids| ids.iter().filter(|id| tas.get_savestate_by_id(**id).is_some()).count());
+    match get_current(tas, slot) {
+        Some(info) => format!(
+            "Slot {}: #{} {} ({}, {} in history)",
+            slot,
+            info.id,
+            info.nickname,
+            info.created.format("%H:%M:%S %d/%m/%Y"),
+            history - 1
+        ),
+        None => format!("Slot {}: empty", slot),
+    }
+}
+
+pub fn print_slots(tas: &Tas) {
+    console::write_line(&Color::Magenta, true, &format!("Slots of {}:", tas.name));
+    for slot in 0..SLOT_COUNT {
+        let color = if get_current(tas, slot).is_some() { Color::Cyan } else { Color::DarkGrey };
+        console::write_line(&color, false, &format!("  {}", describe_slot(tas, slot)));
+    }
+}
+
+fn choose_slot() -> u8 {
+    console::get_int_input(&format!("Enter a slot number (0-{}): ", SLOT_COUNT - 1), 0, SLOT_COUNT as i32 - 1) as u8
+}
+
+// Let the user save to, load or revert a slot. Returns a savestate
+// to load if the user chose to load a slot.
+pub fn slots_menu(tas: &mut Tas) -> Option<PathBuf> {
+    print_slots(tas);
+    let choices = vec![
+        "Save the latest world to a slot".to_string(),
+        "Load a slot".to_string(),
+        "Revert a slot to its previous savestate".to_string(),
+    ];
+    let choice = console::present_choices("Choose a slot action".to_string(), choices);
+    let slot = choose_slot();
+    match choice {
+        0 => match save_to_slot(tas, slot) {
+            Ok(_) => console::write_line(&Color::Green, true, &describe_slot(tas, slot)),
+            Err(e) => console::write_line(&Color::Red, true, &e),
+        },
+        1 => match get_slot_path(tas, slot) {
+            Ok(savestate) => return Some(savestate),
+            Err(e) => console::write_line(&Color::Red, true, &e),
+        },
+        _ => match revert_slot(tas, slot) {
+            Ok(_) => console::write_line(&Color::Green, true, &describe_slot(tas, slot)),
+            Err(e) => console::write_line(&Color::Red, true, &e),
+        },
+    }
+    None
+}
@@ -0,0 +1,221 @@
+use crate::console;
+use crate::tas::Tas;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Duration, Utc};
+use crossterm::style::Color;
+
+// How many segments to list as the most reset
+const MOST_RESET_COUNT: usize = 5;
+// Attempts open for longer than this were left open by a load from the
+// command line or the API and are abandoned instead of timed
+const MAX_ATTEMPT_HOURS: i64 = 12;
+
+// What ended an attempt
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum EndReason {
+    // Another savestate was loaded, i.e. a reset
+    Load,
+    // A new savestate was created, i.e. the segment was completed
+    Create { folder: String },
+    // The session was closed or switched to another TAS
+    Exit,
+    // The program stopped without ending the attempt, so how long it
+    // was played is unknown
+    Abandoned,
+}
+
+// One load of a savestate and how long it was played
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Attempt {
+    pub savestate: String,
+    pub loaded: DateTime<Utc>,
+    #[serde(default)]
+    pub ended: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub ended_by: Option<EndReason>,
+}
+
+impl Attempt {
+    pub fn duration(&self) -> Option<Duration> {
+        self.ended.map(|ended| ended - self.loaded)
+    }
+
+    pub fn seconds(&self) -> Option<i64> {
+        self.duration().map(|duration| duration.num_seconds())
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SavestateStats {
+    pub savestate: String,
+    pub attempts: usize,
+    pub resets: usize,
+    pub completions: usize,
+    pub seconds_played: i64,
+    // Fastest attempt that reached the next savestate
+    pub best_seconds: Option<i64>,
+}
+
+fn get_log_path(tas_folder: &Path) -> PathBuf {
+    tas_folder.join("attempts.json")
+}
+
+// The attempts log of a TAS. A log that can't be read is an error, so
+// that it isn't overwritten by a new attempt.
+pub fn get_attempts(tas_folder: &Path) -> io::Result<Vec<Attempt>> {
+    match File::open(get_log_path(tas_folder)) {
+        Ok(file) => serde_json::from_reader(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn get_attempts_or_warn(tas_folder: &Path) -> Option<Vec<Attempt>> {
+    match get_attempts(tas_folder) {
+        Ok(attempts) => Some(attempts),
+        Err(e) => {
+            console::write_line(&Color::Yellow, true, &format!("Not logging the attempt, failed to read {}: {}", get_log_path(tas_folder).display(), e));
+            None
+        }
+    }
+}
+
+fn save_attempts(tas_folder: &Path, attempts: &[Attempt]) {
+    let file = File::create(get_log_path(tas_folder)).unwrap();
+    serde_json::to_writer(file, attempts).unwrap();
+}
+
+// Log a load of a savestate
+pub fn start_attempt(tas_folder: &Path, savestate: &str) {
+    let Some(mut attempts) = get_attempts_or_warn(tas_folder) else {
+        return;
+    };
+    attempts.push(Attempt {
+        savestate: savestate.to_string(),
+        loaded: Utc::now(),
+        ended: None,
+        ended_by: None,
+    });
+    save_attempts(tas_folder, &attempts);
+}
+
+// End the attempt in progress, if any, and return it. An attempt that
+// has been open for too long is abandoned instead and not returned.
+pub fn end_attempt(tas_folder: &Path, reason: EndReason) -> Option<Attempt> {
+    let mut attempts = get_attempts_or_warn(tas_folder)?;
+    let attempt = match attempts.last_mut() {
+        Some(attempt) if attempt.ended_by.is_none() => attempt,
+        _ => return None,
+    };
+    let now = Utc::now();
+    if now - attempt.loaded > Duration::hours(MAX_ATTEMPT_HOURS) {
+        attempt.ended_by = Some(EndReason::Abandoned);
+        save_attempts(tas_folder, &attempts);
+        return None;
+    }
+    attempt.ended = Some(now);
+    attempt.ended_by = Some(reason);
+    let attempt = attempt.clone();
+    save_attempts(tas_folder, &attempts);
+    Some(attempt)
+}
+
+// Abandon the attempt a previous session left open, e.g. because the
+// program was stopped with Ctrl+C
+pub fn abandon_attempt(tas_folder: &Path) {
+    let Some(mut attempts) = get_attempts_or_warn(tas_folder) else {
+        return;
+    };
+    if let Some(attempt) = attempts.last_mut().filter(|attempt| attempt.ended_by.is_none()) {
+        attempt.ended_by = Some(EndReason::Abandoned);
+        save_attempts(tas_folder, &attempts);
+    }
+}
+
+// Statistics per savestate, in the order the savestates were created
+pub fn compute_stats(tas: &Tas) -> io::Result<Vec<SavestateStats>> {
+    let attempts = get_attempts(&tas.path)?;
+    let mut folders: Vec<String> = tas.savestates.iter().map(|s| s.folder.clone()).collect();
+    // Keep savestates that have since been deleted
+    for attempt in &attempts {
+        if !folders.contains(&attempt.savestate) {
+            folders.push(attempt.savestate.clone());
+        }
+    }
+
+    Ok(folders.into_iter().map(|folder| {
+        let loads: Vec<&Attempt> = attempts.iter().filter(|a| a.savestate == folder).collect();
+        let is_completion = |a: &&&Attempt| matches!(a.ended_by, Some(EndReason::Create { .. }));
+        SavestateStats {
+            attempts: loads.len(),
+            resets: loads.iter().filter(|a| a.ended_by == Some(EndReason::Load)).count(),
+            completions: loads.iter().filter(is_completion).count(),
+            seconds_played: loads.iter().filter_map(|a| a.seconds()).sum(),
+            best_seconds: loads.iter().filter(is_completion).filter_map(|a| a.seconds()).min(),
+            savestate: folder,
+        }
+    }).filter(|stats| stats.attempts > 0).collect())
+}
+
+// Format seconds as e.g. "1h 02m 03s"
+pub fn format_duration(seconds: i64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+fn display_name(tas: &Tas, folder: &str) -> String {
+    match tas.savestates.iter().find(|s| s.folder == folder) {
+        Some(info) => format!("#{} {}", info.id, info.nickname),
+        None => format!("{} (deleted)", folder),
+    }
+}
+
+pub fn print_stats(tas: &Tas) {
+    let stats = match compute_stats(tas) {
+        Ok(stats) => stats,
+        Err(e) => {
+            console::write_line(&Color::Red, true, &format!("Failed to read the attempts log: {}", e));
+            return;
+        }
+    };
+    if stats.is_empty() {
+        console::write_line(&Color::Yellow, true, "No attempts logged yet");
+        return;
+    }
+    let loads: usize = stats.iter().map(|s| s.attempts).sum();
+    let played: i64 = stats.iter().map(|s| s.seconds_played).sum();
+    console::write_line(&Color::Magenta, true, &format!("{}: {} attempts, {} played", tas.name, loads, format_duration(played)));
+    for s in &stats {
+        let best = match s.best_seconds {
+            Some(best) => format!(", best {}", format_duration(best)),
+            None => String::new(),
+        };
+        console::write_line(&Color::Cyan, false, &format!(
+            "  {} {:>4} attempts {:>4} resets {:>3} completed, {} played{}",
+            console::fit_width(&display_name(tas, &s.savestate), 30),
+            s.attempts,
+            s.resets,
+            s.completions,
+            format_duration(s.seconds_played),
+            best
+        ));
+    }
+
+    let mut most_reset: Vec<&SavestateStats> = stats.iter().filter(|s| s.resets > 0).collect();
+    most_reset.sort_by_key(|s| std::cmp::Reverse(s.resets));
+    if !most_reset.is_empty() {
+        console::write_line(&Color::Magenta, true, "Most reset segments:");
+        for s in most_reset.iter().take(MOST_RESET_COUNT) {
+            console::write_line(&Color::Yellow, false, &format!("  {} {} resets", console::fit_width(&display_name(tas, &s.savestate), 30), s.resets));
+        }
+    }
+}
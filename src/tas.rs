@@ -1,184 +1,816 @@
-use crate::dotfile;
-use crate::worlds;
-use crate::console;
-use std::collections::HashMap;
-use std::path::PathBuf;
-use serde::{Serialize, Deserialize};
-use fs_extra::dir::{self, CopyOptions, get_size};
-use crossterm::style::Color;
-use chrono::offset::Utc;
-use chrono::DateTime;
-
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Tas {
-    pub name: String,
-    pub minecraft_folder: PathBuf,
-    pub path: PathBuf,
-    pub num_savestates: usize,
-    pub attempts: HashMap<String, usize>,
-}
-
-impl Tas {
-    pub fn new(name: String, minecraft_folder: PathBuf, path: PathBuf) -> Tas {
-        Tas {
-            name,
-            minecraft_folder,
-            path,
-            num_savestates: 0,
-            attempts: HashMap::new(),
-        }
-    }
-
-    // Copy the world folder to the savestates folder
-    pub fn create_savestate(&mut self, world: PathBuf, nickname: String) {
-        let savestate_name = format!("{}-{}-{}", self.name, self.num_savestates, nickname);
-        let savestate_folder = self.path.join("savestates").join(&savestate_name);
-        std::fs::create_dir(&savestate_folder).unwrap();
-
-        let mut options = CopyOptions::new(); 
-        options.overwrite = true;
-        dir::copy(world.clone(), &savestate_folder, &options).unwrap();
-
-        // Now the world folder is inside the savestate folder
-        // Move contents of world folder to savestate folder and delete world folder
-        let world_folder = savestate_folder.join(world.file_name().unwrap());
-        for entry in std::fs::read_dir(&world_folder).unwrap() {
-            let entry = entry.unwrap();
-            let entry_path = entry.path();
-            let new_path = savestate_folder.join(entry.file_name());
-            std::fs::rename(entry_path, new_path).unwrap();
-        }
-
-        std::fs::remove_dir_all(&world_folder).unwrap();
-
-        self.num_savestates += 1;
-        dotfile::update_tas(self);
-    }
-
-    // Get all savestates for this TAS
-    pub fn get_savestates(&self) -> Vec<PathBuf> {
-        let mut savestates = Vec::new();
-        let savestates_folder = self.path.join("savestates");
-        for entry in std::fs::read_dir(&savestates_folder).unwrap() {
-            savestates.push(entry.unwrap().path());
-        }
-        // Sort by last modified date
-        savestates.sort_by(|a, b| {
-            let a_time = a.metadata().unwrap().modified().unwrap();
-            let b_time = b.metadata().unwrap().modified().unwrap();
-            b_time.cmp(&a_time)
-        });
-
-        savestates
-    }
-
-    // Load a savestate by copying the savestate folder to the .minecraft
-    // saves folder. Return the new path to the savestate folder
-    pub fn load_savestate(&mut self, savestate: &PathBuf) -> PathBuf {
-        let saves_folder = self.minecraft_folder.join("saves");
-        let mut options = CopyOptions::new();
-        options.overwrite = true;
-        // dir::copy(savestate, &saves_folder, &options).unwrap();
-        // Copy the savestate folder to the saves folder with a new name
-        let savestate_name = savestate.file_name().unwrap().to_string_lossy().to_string();
-        if !self.attempts.contains_key(&savestate_name) {
-            self.attempts.insert(savestate_name.clone(), 0);
-        } else {
-            let attempt = self.attempts.get(&savestate_name).unwrap();
-            self.attempts.insert(savestate_name.clone(), attempt + 1);
-        }
-        dotfile::update_tas(self);
-
-        let new_savestate_name = format!("{}-{}", savestate_name, self.attempts.get(&savestate_name).unwrap());
-        let new_savestate = saves_folder.join(&new_savestate_name);
-        std::fs::create_dir(&new_savestate).unwrap();
-        dir::copy(savestate, &new_savestate, &options).unwrap();
-
-        // Move the contents of the new save folder to the saves folder
-        // and delete the new save folder
-        let world_folder = new_savestate.join(savestate_name);
-        for entry in std::fs::read_dir(&world_folder).unwrap() {
-            let entry = entry.unwrap();
-            let entry_path = entry.path();
-            let new_path = new_savestate.join(entry.file_name());
-            std::fs::rename(entry_path, new_path).unwrap();
-        }
-
-        std::fs::remove_dir_all(&world_folder).unwrap();
-
-        new_savestate
-    }
-
-    // Delete a savestate
-    pub fn delete_savestate(&self, savestate: &PathBuf) {
-        // Ensure the folder is a savestate folder
-        if !worlds::is_minecraft_save_folder(savestate) {
-            console::write_line(&Color::Red, true, "Invalid savestate folder, deletion cancelled");
-            return;
-        }
-        // Ensure the folder is < 5GB
-        let size = get_size(savestate).unwrap();
-        if size > 5_000_000_000 {
-            console::write_line(&Color::Red, true, "Savestate is too large to delete automatically for safety reasons. Please delete manually.");
-            return;
-        }
-        // Ensure the folder has 2 hyphens
-        let savestate_name = savestate.file_name().unwrap().to_string_lossy();
-        let hyphens: Vec<&str> = savestate_name.split("-").collect();
-        if hyphens.len() != 3 {
-            console::write_line(&Color::Red, true, "Unfamiliar savestate name format, deletion cancelled");
-            return;
-        }
-
-        std::fs::remove_dir_all(savestate).unwrap();
-    }
-
-    pub fn format_names(savestates: &Vec<PathBuf>) -> Vec<String> {
-        savestates.iter().map(
-            |savestate| {
-                let name = savestate.file_name().unwrap().to_string_lossy().to_string();
-                let last_modified = savestate.metadata().unwrap().modified().unwrap();
-                let datetime: DateTime<Utc> = last_modified.into();
-                let formatted_date = format!("{}", datetime.format("%H:%M:%S %d/%m/%Y"));
-                let limit = 35 - name.len();
-                format!("{}{}{}", name, " ".repeat(limit), formatted_date)
-            }
-        ).collect()
-    }
-
-    // Choose a savestate to load
-    pub fn choose_savestate(&self) -> Option<PathBuf> {
-        let mut savestates = self.get_savestates();
-        if savestates.is_empty() {
-            console::write_line(&Color::Red, true, "No savestates found");
-            return None;
-        }
-        let savestate_names = Tas::format_names(&savestates);
-        let savestate_choice = console::present_choices("Choose a savestate to load".to_string(), savestate_names);
-        Some(savestates[savestate_choice].clone())
-    }
-}
-
-pub fn choose_tas() -> Tas {
-    let tases = dotfile::get_tases();
-    if tases.is_empty() {
-        console::write_line(&Color::Yellow, true, "No TAS files found, creating new TAS file");
-    }
-
-    let mut tas_names: Vec<String> = tases.iter().map(|tas| tas.name.clone()).collect();
-    tas_names.push("Create new TAS file".to_string());
-    let tas_file_choice = console::present_choices("Choose a TAS file to load".to_string(), tas_names.clone());
-    
-    let tas: Tas;
-    if tas_file_choice == tas_names.len() - 1 {
-        let minecraft_folder = worlds::get_chosen_minecraft_folder();
-        tas = dotfile::create_tas(minecraft_folder);
-        console::write_line(&Color::Green, false, &format!("Created new TAS file: {}", tas.name));
-    } else {
-        tas = tases[tas_file_choice].clone();
-        console::write_line(&Color::Green, false, &format!("Loaded TAS file: {}", tas.name));
-    }
-
-
-    tas
+use crate::autosave;
+use crate::dotfile;
+use crate::worlds;
+use crate::console;
+use crate::level::{self, LoadPatch};
+use crate::players::{self, PlayerHandling};
+use crate::hooks::{Event, Hooks};
+use crate::route::RouteSegment;
+use crate::stats::{self, EndReason};
+use crate::server::{self, Target};
+use crate::partial::{self, PartialSpec};
+use crate::verify;
+use crate::trash::{self, TrashKind};
+use crate::oplog::{self, Operation};
+use crate::usage::{self, QuotaMode};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use fs_extra::dir::get_size;
+use crossterm::style::Color;
+use chrono::offset::Utc;
+use chrono::DateTime;
+
+// Metadata for a single savestate. The savestate is identified
+// by its id, never by parsing its folder name.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Savestate {
+    pub id: usize,
+    pub nickname: String,
+    pub folder: String,
+    pub created: DateTime<Utc>,
+    // Id of the savestate a partial savestate is applied on top of
+    #[serde(default)]
+    pub base: Option<usize>,
+    #[serde(default)]
+    pub partial: Option<PartialSpec>,
+    // Ids of the players stored in the savestate, see players::list_players
+    #[serde(default)]
+    pub players: Vec<String>,
+    // Folder of the savestate whose attempt this was created in, and
+    // how long that attempt ran, which is the time of this split
+    #[serde(default)]
+    pub created_from: Option<String>,
+    #[serde(default)]
+    pub segment_seconds: Option<f64>,
+    // Adopted from a folder on disk that may already have been damaged,
+    // so it has no checksums until the user records them
+    #[serde(default)]
+    pub unverified_baseline: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Tas {
+    pub name: String,
+    // The .minecraft folder, or the server folder for server TASes
+    pub minecraft_folder: PathBuf,
+    pub path: PathBuf,
+    pub num_savestates: usize,
+    pub attempts: HashMap<String, usize>,
+    #[serde(default)]
+    pub savestates: Vec<Savestate>,
+    // Check savestates against their checksums before loading them
+    #[serde(default)]
+    pub verify_on_load: bool,
+    // Maximum size of the savestates folder in bytes
+    #[serde(default)]
+    pub quota: Option<u64>,
+    #[serde(default)]
+    pub quota_mode: QuotaMode,
+    // Name of a world in the saves folder that loads replace,
+    // instead of creating a new world for every attempt
+    #[serde(default)]
+    pub working_world: Option<String>,
+    // In-game name given to loaded worlds, with {tas}, {attempt},
+    // {nickname}, {id} and {savestate} replaced. None keeps the name.
+    // New TASes start with the default template, files from before
+    // there were templates keep their world names.
+    #[serde(default)]
+    pub level_name_template: Option<String>,
+    // Named sets of level.dat edits, and the one applied on load
+    #[serde(default)]
+    pub patch_profiles: BTreeMap<String, LoadPatch>,
+    #[serde(default)]
+    pub active_patch_profile: Option<String>,
+    #[serde(default)]
+    pub player_handling: PlayerHandling,
+    #[serde(default)]
+    pub target: Target,
+    #[serde(default)]
+    pub hooks: Hooks,
+    // Ordered segments of the route, each with its canonical savestate
+    #[serde(default)]
+    pub route: Vec<RouteSegment>,
+    // Ids of the savestates saved to each numbered slot, oldest first
+    #[serde(default)]
+    pub slots: BTreeMap<u8, Vec<usize>>,
+    // Minutes between automatic snapshots of the loaded world, None for off
+    #[serde(default)]
+    pub autosave_minutes: Option<u64>,
+    // How many automatic snapshots to keep before dropping the oldest
+    #[serde(default = "default_autosave_keep")]
+    pub autosave_keep: usize,
+    // Command that starts the server. When set, server TASes stop the
+    // server over RCON before a load and run this afterwards.
+    #[serde(default)]
+    pub server_start_command: Option<String>,
+}
+
+// Nicknames of quick saves are this followed by a number
+pub const QUICK_SAVE_NICKNAME: &str = "quick";
+
+pub const DEFAULT_LEVEL_NAME_TEMPLATE: &str = "{tas} #{attempt} {nickname}";
+
+fn default_level_name_template() -> Option<String> {
+    Some(DEFAULT_LEVEL_NAME_TEMPLATE.to_string())
+}
+
+fn default_autosave_keep() -> usize {
+    autosave::DEFAULT_KEEP
+}
+
+impl Tas {
+    pub fn new(name: String, minecraft_folder: PathBuf, path: PathBuf) -> Tas {
+        Tas {
+            name,
+            minecraft_folder,
+            path,
+            num_savestates: 0,
+            attempts: HashMap::new(),
+            savestates: Vec::new(),
+            verify_on_load: false,
+            quota: None,
+            quota_mode: QuotaMode::Warn,
+            working_world: None,
+            level_name_template: default_level_name_template(),
+            patch_profiles: BTreeMap::new(),
+            active_patch_profile: None,
+            player_handling: PlayerHandling::Keep,
+            target: Target::Client,
+            server_start_command: None,
+            hooks: Hooks::default(),
+            route: Vec::new(),
+            slots: BTreeMap::new(),
+            autosave_minutes: None,
+            autosave_keep: default_autosave_keep(),
+        }
+    }
+
+    // Ask the user for the world to save. Servers always use
+    // the world named by level-name in server.properties.
+    pub fn choose_world(&self) -> PathBuf {
+        match self.target {
+            Target::Client => worlds::choose_world(self.minecraft_folder.clone()),
+            Target::Server => server::get_world(&self.minecraft_folder),
+        }
+    }
+
+    // The world the game saved most recently, judged by its level.dat
+    pub fn get_latest_world(&self) -> Option<PathBuf> {
+        if self.target == Target::Server {
+            return Some(server::get_world(&self.minecraft_folder)).filter(|world| world.exists());
+        }
+        let entries = std::fs::read_dir(self.minecraft_folder.join("saves")).ok()?;
+        entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|world| worlds::is_minecraft_save_folder(world))
+            .filter_map(|world| {
+                let modified = world.join("level.dat").metadata().and_then(|m| m.modified()).ok()?;
+                Some((modified, world))
+            })
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, world)| world)
+    }
+
+    // Savestate the latest world without asking for a world
+    pub fn save_latest_world(&mut self, nickname: String) -> Option<PathBuf> {
+        let Some(world) = self.get_latest_world() else {
+            console::write_line(&Color::Red, true, "No world found to save");
+            return None;
+        };
+        console::write_line(&Color::Cyan, false, &format!("Saving {} as {}", world.file_name().unwrap().to_string_lossy(), nickname));
+        self.create_savestate(world, nickname)
+    }
+
+    // The nickname of the next quick save, numbered one past the
+    // highest quick save, e.g. "quick 3"
+    pub fn next_quick_save_nickname(&self) -> String {
+        let number = self.savestates.iter()
+            .filter_map(|s| s.nickname.strip_prefix(QUICK_SAVE_NICKNAME)?.trim().parse::<usize>().ok())
+            .max()
+            .map_or(1, |number| number + 1);
+        format!("{} {}", QUICK_SAVE_NICKNAME, number)
+    }
+
+    // Savestate the latest world without asking anything
+    pub fn quick_save(&mut self) -> Option<PathBuf> {
+        let nickname = self.next_quick_save_nickname();
+        self.save_latest_world(nickname)
+    }
+
+    // Copy the world folder to the savestates folder
+    // Return the path to the new savestate folder, or None if
+    // the TAS quota or the pre_create hook refused it
+    pub fn create_savestate(&mut self, world: PathBuf, nickname: String) -> Option<PathBuf> {
+        self.create_savestate_from(world, nickname, None, None)
+    }
+
+    // Copy part of the world folder to the savestates folder. When loaded,
+    // it is applied on top of the base savestate.
+    pub fn create_partial_savestate(&mut self, world: PathBuf, nickname: String, base: usize, spec: PartialSpec) -> Option<PathBuf> {
+        self.create_savestate_from(world, nickname, Some((base, spec)), None)
+    }
+
+    // Copy a world copied earlier, e.g. an automatic snapshot, to the
+    // savestates folder. It keeps the time it was taken and, not being
+    // the game's current world, doesn't end or start an attempt.
+    pub fn create_savestate_from_snapshot(&mut self, snapshot: PathBuf, nickname: String, taken: DateTime<Utc>) -> Option<PathBuf> {
+        self.create_savestate_from(snapshot, nickname, None, Some(taken))
+    }
+
+    fn create_savestate_from(&mut self, world: PathBuf, nickname: String, partial: Option<(usize, PartialSpec)>, taken: Option<DateTime<Utc>>) -> Option<PathBuf> {
+        let live = taken.is_none();
+        let id = self.num_savestates;
+        let savestate_name = sanitize_folder_name(&format!("{}-{}-{}", self.name, id, nickname));
+        let savestate_folder = self.path.join("savestates").join(&savestate_name);
+        let mut folders = vec![(world.clone(), savestate_folder.clone())];
+        // Snapshots already hold the split dimensions of servers
+        if self.target == Target::Server && live {
+            for suffix in server::SPLIT_DIMENSIONS {
+                let split_folder = server::split_dimension_folder(&world, suffix);
+                if split_folder.exists() {
+                    folders.push((split_folder, savestate_folder.join(suffix)));
+                }
+            }
+        }
+        let spec = partial.as_ref().map(|(_, spec)| spec);
+        let needed = folders.iter()
+            .map(|(from, _)| match spec {
+                Some(spec) => partial::partial_size(from, spec),
+                None => get_size(from).unwrap_or(0),
+            })
+            .sum();
+        if !self.check_quota(needed) {
+            return None;
+        }
+
+        if !self.hooks.run(Event::PreCreate, &self.name, &savestate_folder, Some(&world)) {
+            console::write_line(&Color::Red, true, "Savestate creation cancelled by the pre_create hook");
+            return None;
+        }
+        std::fs::create_dir(&savestate_folder).unwrap();
+
+        let copy = || {
+            for (from, to) in folders {
+                match &partial {
+                    Some((_, spec)) => partial::copy_partial(&from, &to, spec).unwrap(),
+                    None => worlds::copy_world_contents(&from, &to),
+                }
+            }
+        };
+        match self.target {
+            Target::Server if live => server::with_saving_paused(&self.minecraft_folder, copy),
+            _ => copy(),
+        }
+        if let Err(e) = verify::record_manifest(&self.path, &savestate_folder) {
+            console::write_line(&Color::Yellow, true, &format!("Failed to record checksums for the savestate: {}", e));
+        }
+
+        let (base, partial) = match partial {
+            Some((base, spec)) => (Some(base), Some(spec)),
+            None => (None, None),
+        };
+        let attempt = if live {
+            stats::end_attempt(&self.path, EndReason::Create { folder: savestate_name.clone() })
+        } else {
+            None
+        };
+        self.savestates.push(Savestate {
+            id,
+            nickname,
+            folder: savestate_name.clone(),
+            created: taken.unwrap_or_else(Utc::now),
+            base,
+            partial,
+            players: players::list_players(&savestate_folder).into_iter().map(|p| p.id).collect(),
+            unverified_baseline: false,
+            segment_seconds: attempt.as_ref().and_then(|a| a.duration()).map(|d| d.num_milliseconds() as f64 / 1000.0),
+            created_from: attempt.map(|a| a.savestate),
+        });
+        self.num_savestates += 1;
+        dotfile::update_tas(self);
+        // Playing on from the new savestate is an attempt at the next segment
+        if live {
+            stats::start_attempt(&self.path, &savestate_name);
+        }
+        oplog::record(Operation::Create { tas: self.name.clone(), folder: savestate_name });
+        self.hooks.run(Event::PostCreate, &self.name, &savestate_folder, Some(&world));
+
+        Some(savestate_folder)
+    }
+
+    // Check whether copying this many bytes would exceed the quota.
+    // Returns false if the savestate should not be created.
+    fn check_quota(&self, needed: u64) -> bool {
+        let quota = match self.quota {
+            Some(quota) => quota,
+            None => return true,
+        };
+        let used = usage::savestates_size(self);
+        if used + needed <= quota {
+            return true;
+        }
+
+        let message = format!(
+            "This savestate would bring {} to {} of its {} quota",
+            self.name,
+            usage::format_size(used + needed),
+            usage::format_size(quota)
+        );
+        match self.quota_mode {
+            QuotaMode::Warn => {
+                console::write_line(&Color::Yellow, true, &message);
+                true
+            }
+            QuotaMode::Refuse => {
+                console::write_line(&Color::Red, true, &format!("{}, savestate not created", message));
+                false
+            }
+        }
+    }
+
+    // Register savestate folders that have no metadata yet, such as
+    // those created by older versions. Folder names are kept as-is.
+    pub fn adopt_untracked_savestates(&mut self) {
+        let savestates_folder = self.path.join("savestates");
+        let mut adopted = false;
+        for entry in std::fs::read_dir(&savestates_folder).unwrap().flatten() {
+            let folder = entry.file_name().to_string_lossy().to_string();
+            if !entry.path().is_dir() || self.savestates.iter().any(|s| s.folder == folder) {
+                continue;
+            }
+            let created: DateTime<Utc> = entry.metadata().unwrap().modified().unwrap().into();
+            self.savestates.push(Savestate {
+                id: self.num_savestates,
+                nickname: folder.clone(),
+                folder,
+                created,
+                base: None,
+                partial: None,
+                players: players::list_players(&entry.path()).into_iter().map(|p| p.id).collect(),
+                created_from: None,
+                segment_seconds: None,
+                unverified_baseline: true,
+            });
+            self.num_savestates += 1;
+            adopted = true;
+        }
+
+        if adopted {
+            dotfile::update_tas(self);
+        }
+    }
+
+    // Find the metadata for a savestate folder
+    pub fn get_savestate_info(&self, savestate: &Path) -> Option<&Savestate> {
+        if savestate.parent()? != self.path.join("savestates") {
+            return None;
+        }
+        let folder = savestate.file_name()?.to_string_lossy();
+        self.savestates.iter().find(|s| s.folder == folder)
+    }
+
+    // Get all savestates for this TAS, newest first. Tracked savestates are
+    // ordered by id, folders the TAS doesn't know about come last by name.
+    pub fn get_savestates(&self) -> Vec<PathBuf> {
+        let savestates_folder = self.path.join("savestates");
+        let mut savestates: Vec<PathBuf> = std::fs::read_dir(&savestates_folder).into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        savestates.sort_by(|a, b| {
+            let a_id = self.get_savestate_info(a).map(|info| info.id);
+            let b_id = self.get_savestate_info(b).map(|info| info.id);
+            b_id.cmp(&a_id).then_with(|| a.cmp(b))
+        });
+
+        savestates
+    }
+
+    // The most recently created savestate
+    pub fn get_latest_savestate(&self) -> Option<PathBuf> {
+        let latest = self.savestates.iter().max_by_key(|s| (s.created, s.id))?;
+        Some(self.path.join("savestates").join(&latest.folder))
+    }
+
+    pub fn get_savestate_by_id(&self, id: usize) -> Option<&Savestate> {
+        self.savestates.iter().find(|s| s.id == id)
+    }
+
+    // Get the folders that make up a savestate, starting from the
+    // full savestate at the root and ending with the savestate itself.
+    // Returns None if a base savestate is missing.
+    pub fn get_savestate_chain(&self, savestate: &Path) -> Option<Vec<PathBuf>> {
+        let savestates_folder = self.path.join("savestates");
+        let mut chain = vec![savestate.to_path_buf()];
+        let mut base = self.get_savestate_info(savestate).and_then(|info| info.base);
+        while let Some(id) = base {
+            let info = self.get_savestate_by_id(id)?;
+            if chain.len() > self.savestates.len() {
+                return None;
+            }
+            chain.push(savestates_folder.join(&info.folder));
+            base = info.base;
+        }
+        chain.reverse();
+        Some(chain)
+    }
+
+    // Get the savestate a savestate was created from, the one that was
+    // created from, and so on, starting from the oldest and ending with
+    // the savestate itself
+    pub fn get_ancestry<'a>(&'a self, savestate: &'a Savestate) -> Vec<&'a Savestate> {
+        let mut ancestry = vec![savestate];
+        while let Some(from) = ancestry.last().unwrap().created_from.as_deref() {
+            match self.savestates.iter().find(|s| s.folder == from) {
+                // Savestates are only created from older ones, so this ends
+                Some(parent) if parent.id < ancestry.last().unwrap().id => ancestry.push(parent),
+                _ => break,
+            }
+        }
+        ancestry.reverse();
+        ancestry
+    }
+
+    // Load a savestate by copying the savestate folder to the .minecraft
+    // saves folder, or over the working world if one is set. Partial
+    // savestates are copied on top of their bases.
+    // Return the new path to the savestate folder
+    pub fn load_savestate(&mut self, savestate: &Path) -> Option<PathBuf> {
+        let chain = match self.get_savestate_chain(savestate) {
+            Some(chain) => chain,
+            None => {
+                console::write_line(&Color::Red, true, "The base of this partial savestate no longer exists, load cancelled");
+                return None;
+            }
+        };
+        if self.verify_on_load {
+            for folder in &chain {
+                let name = folder.file_name().unwrap().to_string_lossy();
+                if !verify::print_report(&name, &verify::verify_savestate(&self.path, folder)) {
+                    console::write_line(&Color::Red, true, "Savestate failed verification, load cancelled");
+                    return None;
+                }
+            }
+        }
+        if !self.hooks.run(Event::PreLoad, &self.name, savestate, None) {
+            console::write_line(&Color::Red, true, "Load cancelled by the pre_load hook");
+            return None;
+        }
+
+        // Copy the savestate folder to the saves folder with a new name
+        let saves_folder = self.minecraft_folder.join("saves");
+        let savestate_name = savestate.file_name().unwrap().to_string_lossy().to_string();
+        match self.attempts.get(&savestate_name) {
+            Some(attempt) => {
+                self.attempts.insert(savestate_name.clone(), attempt + 1);
+            }
+            None => {
+                self.attempts.insert(savestate_name.clone(), 0);
+            }
+        }
+        dotfile::update_tas(self);
+
+        let world = match self.working_world.clone() {
+            _ if self.target == Target::Server => self.load_savestate_to_server(chain, savestate_name.clone())?,
+            Some(working_world) => self.load_savestate_in_place(chain, savestate_name.clone(), saves_folder.join(working_world))?,
+            None => self.load_savestate_as_attempt(chain, savestate_name.clone(), &saves_folder),
+        };
+        self.rename_loaded_world(savestate, &savestate_name, &world);
+        // Stripping removes the player that patches edit, so patch first
+        self.patch_loaded_world(&world);
+        if let Err(e) = players::apply_handling(&world, &self.player_handling) {
+            console::write_line(&Color::Yellow, true, &format!("Failed to {} in the loaded world: {}", self.player_handling.describe(), e));
+        }
+        stats::end_attempt(&self.path, EndReason::Load);
+        stats::start_attempt(&self.path, &savestate_name);
+        self.hooks.run(Event::PostLoad, &self.name, savestate, Some(&world));
+
+        if self.target == Target::Server {
+            if let Some(command) = &self.server_start_command {
+                match server::start_server(&self.minecraft_folder, command) {
+                    Ok(()) => console::write_line(&Color::Green, true, "Server started"),
+                    Err(e) => console::write_line(&Color::Red, true, &format!("Failed to start the server: {}", e)),
+                }
+            }
+        }
+
+        Some(world)
+    }
+
+    // Copy the savestate into a new world named <savestate>-<attempt>
+    fn load_savestate_as_attempt(&mut self, chain: Vec<PathBuf>, savestate_name: String, saves_folder: &Path) -> PathBuf {
+        let new_savestate_name = format!("{}-{}", savestate_name, self.attempts.get(&savestate_name).unwrap());
+        let new_savestate = saves_folder.join(&new_savestate_name);
+        std::fs::create_dir(&new_savestate).unwrap();
+        for folder in chain {
+            worlds::copy_world_contents(&folder, &new_savestate);
+        }
+        oplog::record(Operation::Load { tas: self.name.clone(), savestate: savestate_name, world: new_savestate.clone() });
+
+        new_savestate
+    }
+
+    // Apply the active patch profile to a loaded world
+    fn patch_loaded_world(&self, world: &Path) {
+        let name = match &self.active_patch_profile {
+            Some(name) => name,
+            None => return,
+        };
+        let patch = match self.patch_profiles.get(name) {
+            Some(patch) => patch,
+            None => {
+                console::write_line(&Color::Yellow, true, &format!("Patch profile {} no longer exists", name));
+                return;
+            }
+        };
+        match level::apply_patch(world, patch) {
+            Ok(()) => console::write_line(&Color::Green, false, &format!("Applied patch profile {}: {}", name, patch.describe())),
+            Err(e) => console::write_line(&Color::Yellow, true, &format!("Failed to apply patch profile {}: {}", name, e)),
+        }
+    }
+
+    // Replace the server's world folders with the savestate. The
+    // replaced folders become the TAS's load backup. The server
+    // is stopped first if it is to be restarted after the load.
+    fn load_savestate_to_server(&mut self, chain: Vec<PathBuf>, savestate_name: String) -> Option<PathBuf> {
+        if self.server_start_command.is_some() {
+            if let Err(e) = server::stop_server(&self.minecraft_folder) {
+                console::write_line(&Color::Red, true, &format!("Failed to stop the server, load cancelled: {}", e));
+                self.remove_attempt(&savestate_name);
+                return None;
+            }
+        } else if server::is_running(&self.minecraft_folder) {
+            // The server would keep playing the old world and overwrite the loaded one
+            console::write_line(&Color::Red, true, "The server is running, stop it or set a command to restart it around loads, load cancelled");
+            self.remove_attempt(&savestate_name);
+            return None;
+        }
+
+        let world = server::get_world(&self.minecraft_folder);
+        let mut worlds = vec![world.clone()];
+        worlds.extend(server::SPLIT_DIMENSIONS.iter().map(|suffix| server::split_dimension_folder(&world, suffix)));
+
+        let backup = match trash::replace_load_backup(&self.path, &worlds) {
+            Ok(backup) => backup,
+            Err(e) => {
+                console::write_line(&Color::Red, true, &format!("Failed to back up the server world, load cancelled: {}", e));
+                self.remove_attempt(&savestate_name);
+                return None;
+            }
+        };
+
+        std::fs::create_dir(&world).unwrap();
+        for folder in chain {
+            worlds::copy_world_contents(&folder, &world);
+        }
+        // Move split dimensions out to where the server expects them
+        for suffix in server::SPLIT_DIMENSIONS {
+            let inner = world.join(suffix);
+            if inner.exists() {
+                std::fs::rename(&inner, server::split_dimension_folder(&world, suffix)).unwrap();
+            }
+        }
+        oplog::record(Operation::LoadServer { tas: self.name.clone(), savestate: savestate_name, worlds, backups: vec![backup] });
+
+        Some(world)
+    }
+
+    // Set the in-game name of a loaded world from the level name template
+    fn rename_loaded_world(&self, savestate: &Path, savestate_name: &str, world: &Path) {
+        let template = match &self.level_name_template {
+            Some(template) => template,
+            None => return,
+        };
+        let attempt = self.attempts.get(savestate_name).copied().unwrap_or(0);
+        let (id, nickname) = match self.get_savestate_info(savestate) {
+            Some(info) => (info.id.to_string(), info.nickname.clone()),
+            None => (String::new(), savestate_name.to_string()),
+        };
+        let level_name = fill_template(template, &[
+            ("tas", &self.name),
+            ("attempt", &attempt.to_string()),
+            ("nickname", &nickname),
+            ("id", &id),
+            ("savestate", savestate_name),
+        ]);
+        if let Err(e) = level::set_level_name(world, &level_name) {
+            console::write_line(&Color::Yellow, true, &format!("Failed to rename the loaded world: {}", e));
+        }
+    }
+
+    // Replace the working world with the savestate, keeping its folder
+    // name so the game sees the same world. The replaced world becomes
+    // the TAS's load backup.
+    fn load_savestate_in_place(&mut self, chain: Vec<PathBuf>, savestate_name: String, world: PathBuf) -> Option<PathBuf> {
+        let backup = if world.exists() {
+            match trash::replace_load_backup(&self.path, std::slice::from_ref(&world)) {
+                Ok(backup) => Some(backup),
+                Err(e) => {
+                    console::write_line(&Color::Red, true, &format!("Failed to back up the working world, load cancelled: {}", e));
+                    self.remove_attempt(&savestate_name);
+                    return None;
+                }
+            }
+        } else {
+            None
+        };
+
+        std::fs::create_dir(&world).unwrap();
+        for folder in chain {
+            worlds::copy_world_contents(&folder, &world);
+        }
+        oplog::record(Operation::LoadInPlace { tas: self.name.clone(), savestate: savestate_name, world: world.clone(), backup });
+
+        Some(world)
+    }
+
+    // Delete a savestate by moving it to the trash
+    // Return the id of the trash entry
+    pub fn delete_savestate(&mut self, savestate: &Path) -> Option<String> {
+        // Ensure the folder is a savestate of this TAS
+        let info = match self.get_savestate_info(savestate) {
+            Some(info) => info.clone(),
+            None => {
+                console::write_line(&Color::Red, true, "Unknown savestate, deletion cancelled");
+                return None;
+            }
+        };
+        let id = info.id;
+        // Ensure no partial savestate is built on top of it
+        if let Some(dependent) = self.savestates.iter().find(|s| s.base == Some(id)) {
+            console::write_line(&Color::Red, true, &format!("Partial savestate #{} {} depends on this savestate, deletion cancelled", dependent.id, dependent.nickname));
+            return None;
+        }
+        // Ensure the folder is a savestate folder
+        if !worlds::is_minecraft_save_folder(savestate) {
+            console::write_line(&Color::Red, true, "Invalid savestate folder, deletion cancelled");
+            return None;
+        }
+        // Ensure the folder is < 5GB
+        let size = get_size(savestate).unwrap();
+        if size > 5_000_000_000 {
+            console::write_line(&Color::Red, true, "Savestate is too large to delete automatically for safety reasons. Please delete manually.");
+            return None;
+        }
+
+        let trash_id = match self.trash_savestate(&info) {
+            Ok(trash_id) => trash_id,
+            Err(e) => {
+                console::write_line(&Color::Red, true, &format!("Failed to move the savestate to the trash: {}", e));
+                return None;
+            }
+        };
+        oplog::record(Operation::DeleteSavestate { tas: self.name.clone(), folder: info.folder, trash_id: trash_id.clone() });
+        self.hooks.run(Event::PostDelete, &self.name, &trash::get_trash_path().join(&trash_id), None);
+
+        Some(trash_id)
+    }
+
+    // Move a savestate to the trash and forget its metadata, without
+    // any of the safety checks done by delete_savestate
+    pub fn trash_savestate(&mut self, info: &Savestate) -> std::io::Result<String> {
+        let savestate = self.path.join("savestates").join(&info.folder);
+        let trash_id = trash::move_to_trash(&savestate, TrashKind::Savestate { tas: self.name.clone(), info: Box::new(info.clone()) })?;
+        self.savestates.retain(|s| s.folder != info.folder);
+        dotfile::update_tas(self);
+        Ok(trash_id)
+    }
+
+    // Take back the most recent attempt counted for a savestate
+    pub fn remove_attempt(&mut self, savestate_name: &str) {
+        match self.attempts.get(savestate_name) {
+            Some(0) => {
+                self.attempts.remove(savestate_name);
+            }
+            Some(attempt) => {
+                self.attempts.insert(savestate_name.to_string(), attempt - 1);
+            }
+            None => {}
+        }
+        dotfile::update_tas(self);
+    }
+
+    // Accept the current contents of an adopted savestate as its baseline
+    pub fn record_baseline(&mut self, savestate: &Path) -> std::io::Result<()> {
+        verify::record_manifest(&self.path, savestate)?;
+        let folder = savestate.file_name().unwrap().to_string_lossy();
+        if let Some(info) = self.savestates.iter_mut().find(|s| s.folder == folder) {
+            info.unverified_baseline = false;
+        }
+        dotfile::update_tas(self);
+        Ok(())
+    }
+
+    // Verify every savestate of this TAS against its checksums.
+    // Returns true if none failed.
+    pub fn verify_all(&self) -> bool {
+        let mut ok = true;
+        for savestate in self.get_savestates() {
+            let name = savestate.file_name().unwrap().to_string_lossy().to_string();
+            ok &= verify::print_report(&name, &verify::verify_savestate(&self.path, &savestate));
+        }
+        ok
+    }
+
+    // Format savestates for display as "#id nickname", padded
+    // so that the last modified dates line up
+    pub fn format_names(&self, savestates: &[PathBuf]) -> Vec<String> {
+        savestates.iter().map(
+            |savestate| {
+                let name = match self.get_savestate_info(savestate) {
+                    Some(Savestate { id, nickname, base: Some(base), .. }) => format!("#{} {} (on #{})", id, nickname, base),
+                    Some(info) => format!("#{} {}", info.id, info.nickname),
+                    None => savestate.file_name().unwrap().to_string_lossy().to_string(),
+                };
+                // The folder may have been removed since it was listed
+                let formatted_date = match savestate.metadata().and_then(|metadata| metadata.modified()) {
+                    Ok(last_modified) => DateTime::<Utc>::from(last_modified).format("%H:%M:%S %d/%m/%Y").to_string(),
+                    Err(_) => String::new(),
+                };
+                format!("{} {}", console::fit_width(&name, 34), formatted_date)
+            }
+        ).collect()
+    }
+
+    // Ask the user to choose a savestate
+    pub fn choose_savestate(&self, prompt: &str) -> Option<PathBuf> {
+        let savestates = self.get_savestates();
+        if savestates.is_empty() {
+            console::write_line(&Color::Red, true, "No savestates found");
+            return None;
+        }
+        let savestate_names = self.format_names(&savestates);
+        let savestate_choice = console::present_choices(prompt.to_string(), savestate_names);
+        Some(savestates[savestate_choice].clone())
+    }
+}
+
+pub fn choose_tas() -> Tas {
+    let tases = dotfile::get_tases();
+    if tases.is_empty() {
+        console::write_line(&Color::Yellow, true, "No TAS files found, creating new TAS file");
+    }
+
+    let mut tas_names: Vec<String> = tases.iter().map(|tas| tas.name.clone()).collect();
+    tas_names.push("Create new TAS file".to_string());
+    let tas_file_choice = console::present_choices("Choose a TAS file to load".to_string(), tas_names.clone());
+    
+    let mut tas: Tas;
+    if tas_file_choice == tas_names.len() - 1 {
+        let targets = vec![
+            "Worlds in a .minecraft folder".to_string(),
+            "A dedicated server world".to_string(),
+        ];
+        let (folder, target) = match console::present_choices("What will this TAS save?".to_string(), targets) {
+            0 => (worlds::get_chosen_minecraft_folder(), Target::Client),
+            _ => (server::choose_server_folder(), Target::Server),
+        };
+        tas = dotfile::create_tas(folder, target);
+        console::write_line(&Color::Green, false, &format!("Created new TAS file: {}", tas.name));
+    } else {
+        tas = tases[tas_file_choice].clone();
+        tas.adopt_untracked_savestates();
+        console::write_line(&Color::Green, false, &format!("Loaded TAS file: {}", tas.name));
+    }
+
+
+    tas
+}
+
+// Replace each {name} in a template with its value. Values are inserted
+// as-is, so placeholders inside them are not expanded again.
+pub fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            values.iter().find(|(name, _)| *name == &rest[1..end]).map(|(_, value)| (end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                filled.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+// Replace characters that are not allowed in folder names
+// on common filesystems. Unicode and hyphens are kept.
+pub fn sanitize_folder_name(name: &str) -> String {
+    name.chars().map(|c| match c {
+        '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+        c if c.is_control() => '_',
+        c => c,
+    }).collect::<String>().trim_end_matches(['.', ' ']).to_string()
 }
\ No newline at end of file
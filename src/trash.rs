@@ -0,0 +1,305 @@
+use crate::console;
+use crate::dotfile;
+use crate::tas::{self, Savestate, Tas};
+use crate::verify;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Duration, Utc};
+use crossterm::style::Color;
+
+// Items older than this are purged automatically on startup
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum TrashKind {
+    // A savestate, with the metadata needed to put it back in its TAS
+    Savestate { tas: String, info: Box<Savestate> },
+    // A world from a .minecraft saves folder or server folder
+    World,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: TrashKind,
+    pub original_path: PathBuf,
+    pub deleted: DateTime<Utc>,
+}
+
+impl TrashEntry {
+    pub fn path(&self) -> PathBuf {
+        get_trash_path().join(&self.id)
+    }
+
+    pub fn describe(&self) -> String {
+        let name = self.original_path.file_name().unwrap_or_default().to_string_lossy();
+        let kind = match &self.kind {
+            TrashKind::Savestate { tas, .. } => format!("savestate of {}", tas),
+            TrashKind::World => "world".to_string(),
+        };
+        let deleted = self.deleted.format("%H:%M:%S %d/%m/%Y");
+        format!("{} {} ({}) {}", console::fit_width(&name, 34), deleted, kind, self.original_path.display())
+    }
+}
+
+pub fn get_trash_path() -> PathBuf {
+    dotfile::get_dotfile_path().join("trash")
+}
+
+fn get_index_path() -> PathBuf {
+    get_trash_path().join("trash.json")
+}
+
+// The items in the trash. An index that can't be read is an error, so
+// that it isn't overwritten and the items it lists stay restorable.
+pub fn get_entries() -> io::Result<Vec<TrashEntry>> {
+    match File::open(get_index_path()) {
+        Ok(file) => serde_json::from_reader(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn save_entries(entries: &[TrashEntry]) -> io::Result<()> {
+    std::fs::create_dir_all(get_trash_path())?;
+    let file = File::create(get_index_path())?;
+    serde_json::to_writer(file, entries).map_err(io::Error::other)
+}
+
+// Move a folder, falling back to copy and delete when
+// the destination is on another drive
+fn move_folder(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(to)?;
+    let mut options = fs_extra::dir::CopyOptions::new();
+    options.content_only = true;
+    fs_extra::dir::copy(from, to, &options).map_err(|e| io::Error::other(e.to_string()))?;
+    std::fs::remove_dir_all(from)
+}
+
+// Loads over the working world or a server world keep what they replaced
+// here instead of in the trash. Each load overwrites the previous backup,
+// so only the latest load can be undone, but repeated loads don't pile up
+// full world copies.
+pub fn get_load_backup_path(tas_folder: &Path) -> PathBuf {
+    tas_folder.join("load-backup")
+}
+
+// Replace the load backup of a TAS with the given world folders, moving
+// them out of the way. The previous backup is only deleted once the new
+// one is in place, and is kept if moving the worlds fails. Returns the
+// id of the new backup.
+pub fn replace_load_backup(tas_folder: &Path, worlds: &[PathBuf]) -> io::Result<String> {
+    let backup_path = get_load_backup_path(tas_folder);
+    let previous_path = tas_folder.join("load-backup.previous");
+    if previous_path.exists() {
+        std::fs::remove_dir_all(&previous_path)?;
+    }
+    if backup_path.exists() {
+        std::fs::rename(&backup_path, &previous_path)?;
+    }
+    let id = Utc::now().format("%Y%m%d%H%M%S%3f").to_string();
+    let folder = backup_path.join(&id);
+    let mut moved: Vec<&PathBuf> = vec![];
+    let result = (|| {
+        std::fs::create_dir_all(&folder)?;
+        for world in worlds.iter().filter(|world| world.exists()) {
+            move_folder(world, &folder.join(world.file_name().unwrap()))?;
+            moved.push(world);
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        // Put back what was already moved, so that nothing is half backed up
+        for world in moved {
+            let _ = move_folder(&folder.join(world.file_name().unwrap()), world);
+        }
+        let _ = std::fs::remove_dir_all(&backup_path);
+        if previous_path.exists() {
+            let _ = std::fs::rename(&previous_path, &backup_path);
+        }
+        return Err(e);
+    }
+    if previous_path.exists() {
+        std::fs::remove_dir_all(&previous_path)?;
+    }
+    Ok(id)
+}
+
+// Move the worlds of a load backup back to where they were
+pub fn restore_load_backup(tas_folder: &Path, id: &str, worlds: &[PathBuf]) -> Result<(), String> {
+    let folder = get_load_backup_path(tas_folder).join(id);
+    if !folder.exists() {
+        return Err("The backup of the worlds it replaced was overwritten by a later load".to_string());
+    }
+    for world in worlds {
+        let backup = folder.join(world.file_name().unwrap());
+        if backup.exists() {
+            move_folder(&backup, world).map_err(|e| e.to_string())?;
+        }
+    }
+    std::fs::remove_dir_all(folder).map_err(|e| e.to_string())
+}
+
+// Move a folder into the trash and record where it came from.
+// Returns the id of the trash entry.
+pub fn move_to_trash(path: &Path, kind: TrashKind) -> io::Result<String> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let id = tas::sanitize_folder_name(&format!("{}-{}", Utc::now().format("%Y%m%d%H%M%S%3f"), name));
+    let mut entries = get_entries()?;
+    std::fs::create_dir_all(get_trash_path())?;
+    move_folder(path, &get_trash_path().join(&id))?;
+
+    entries.push(TrashEntry {
+        id: id.clone(),
+        kind,
+        original_path: path.to_path_buf(),
+        deleted: Utc::now(),
+    });
+    save_entries(&entries)?;
+
+    Ok(id)
+}
+
+// Move a trashed folder back to where it came from. Savestates are
+// registered with their TAS again, using the in-memory TAS if it is the
+// one currently open so that its state is not overwritten later.
+pub fn restore(id: &str, current: &mut Tas) -> Result<PathBuf, String> {
+    let mut entries = get_entries().map_err(|e| format!("Failed to read the trash index: {}", e))?;
+    let index = entries.iter().position(|e| e.id == id).ok_or("No such item in the trash")?;
+    let entry = entries[index].clone();
+    if entry.original_path.exists() {
+        return Err(format!("{} already exists", entry.original_path.display()));
+    }
+
+    if let TrashKind::Savestate { tas: tas_name, info } = &entry.kind {
+        let mut other;
+        let tas = if *tas_name == current.name {
+            current
+        } else {
+            other = dotfile::get_tases().into_iter().find(|t| t.name == *tas_name)
+                .ok_or(format!("The TAS {} no longer exists", tas_name))?;
+            &mut other
+        };
+        move_folder(&entry.path(), &entry.original_path).map_err(|e| e.to_string())?;
+        if !tas.savestates.iter().any(|s| s.folder == info.folder) {
+            tas.savestates.push(info.as_ref().clone());
+        }
+        dotfile::update_tas(tas);
+    } else {
+        std::fs::create_dir_all(entry.original_path.parent().unwrap()).map_err(|e| e.to_string())?;
+        move_folder(&entry.path(), &entry.original_path).map_err(|e| e.to_string())?;
+    }
+
+    entries.remove(index);
+    save_entries(&entries).map_err(|e| e.to_string())?;
+    Ok(entry.original_path)
+}
+
+// Permanently delete a trash entry, including any checksums kept for it
+fn remove_entry(entry: &TrashEntry) {
+    let _ = std::fs::remove_dir_all(entry.path());
+    if let TrashKind::Savestate { tas, .. } = &entry.kind {
+        let tas_folder = dotfile::get_dotfile_path().join("tases").join(tas);
+        verify::remove_manifest(&tas_folder, &entry.original_path);
+    }
+}
+
+// Permanently delete trash entries older than the given number of days.
+// Returns how many were deleted.
+pub fn purge_older_than(days: i64) -> io::Result<usize> {
+    let cutoff = Utc::now() - Duration::days(days);
+    let (old, kept): (Vec<TrashEntry>, Vec<TrashEntry>) = get_entries()?.into_iter().partition(|e| e.deleted < cutoff);
+    for entry in &old {
+        remove_entry(entry);
+    }
+    if !old.is_empty() {
+        save_entries(&kept)?;
+    }
+    Ok(old.len())
+}
+
+// Permanently delete everything in the trash
+pub fn empty() -> io::Result<usize> {
+    let entries = get_entries()?;
+    for entry in &entries {
+        remove_entry(entry);
+    }
+    save_entries(&[])?;
+    Ok(entries.len())
+}
+
+fn get_entries_or_report() -> Option<Vec<TrashEntry>> {
+    match get_entries() {
+        Ok(entries) => Some(entries),
+        Err(e) => {
+            console::write_line(&Color::Red, true, &format!("Failed to read {}: {}", get_index_path().display(), e));
+            None
+        }
+    }
+}
+
+fn choose_entry(prompt: &str) -> Option<TrashEntry> {
+    let mut entries = get_entries_or_report()?;
+    if entries.is_empty() {
+        console::write_line(&Color::Yellow, true, "The trash is empty");
+        return None;
+    }
+    entries.reverse();
+    let names: Vec<String> = entries.iter().map(|e| e.describe()).collect();
+    let choice = console::present_choices(prompt.to_string(), names);
+    Some(entries[choice].clone())
+}
+
+// Let the user list, restore or permanently delete trashed items
+pub fn trash_menu(tas: &mut Tas) {
+    let choices = vec![
+        "List items in the trash".to_string(),
+        "Restore an item".to_string(),
+        format!("Empty items older than {} days", DEFAULT_RETENTION_DAYS),
+        "Empty the trash".to_string(),
+    ];
+    match console::present_choices("Choose a trash action".to_string(), choices) {
+        0 => {
+            let Some(entries) = get_entries_or_report() else {
+                return;
+            };
+            if entries.is_empty() {
+                console::write_line(&Color::Yellow, true, "The trash is empty");
+            }
+            for entry in entries.iter().rev() {
+                console::write_line(&Color::Cyan, false, &entry.describe());
+            }
+        }
+        1 => {
+            if let Some(entry) = choose_entry("Choose an item to restore") {
+                match restore(&entry.id, tas) {
+                    Ok(path) => console::write_line(&Color::Green, true, &format!("Restored {}", path.display())),
+                    Err(e) => console::write_line(&Color::Red, true, &format!("Failed to restore: {}", e)),
+                }
+            }
+        }
+        2 => match purge_older_than(DEFAULT_RETENTION_DAYS) {
+            Ok(purged) => console::write_line(&Color::Green, true, &format!("Permanently deleted {} items", purged)),
+            Err(e) => console::write_line(&Color::Red, true, &format!("Failed to empty the trash: {}", e)),
+        },
+        _ => {
+            let Some(entries) = get_entries_or_report() else {
+                return;
+            };
+            if console::confirm(format!("Are you sure you want to permanently delete {} items?", entries.len()), "delete") {
+                match empty() {
+                    Ok(emptied) => console::write_line(&Color::Green, true, &format!("Permanently deleted {} items", emptied)),
+                    Err(e) => console::write_line(&Color::Red, true, &format!("Failed to empty the trash: {}", e)),
+                }
+            } else {
+                console::write_line(&Color::Yellow, true, "Trash not emptied");
+            }
+        }
+    }
+}
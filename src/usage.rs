@@ -0,0 +1,117 @@
+use crate::autosave;
+use crate::console;
+use crate::dotfile;
+use crate::tas::Tas;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use fs_extra::dir::get_size;
+use crossterm::style::Color;
+
+// What to do when creating a savestate would exceed the TAS quota
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum QuotaMode {
+    #[default]
+    Warn,
+    Refuse,
+}
+
+// Format a byte count as e.g. "1.5 GB"
+pub fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < units.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+fn size_of(path: &Path) -> u64 {
+    get_size(path).unwrap_or(0)
+}
+
+// Total size of all savestates in a TAS
+pub fn savestates_size(tas: &Tas) -> u64 {
+    size_of(&tas.path.join("savestates"))
+}
+
+// Find the worlds in the saves folder that were loaded from this
+// TAS's savestates, i.e. named <savestate folder>-<attempt>
+pub fn get_attempt_worlds(tas: &Tas) -> Vec<PathBuf> {
+    let saves_folder = tas.minecraft_folder.join("saves");
+    let mut worlds = vec![];
+    if let Ok(entries) = std::fs::read_dir(saves_folder) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_attempt = tas.savestates.iter().any(|s| {
+                name.strip_prefix(&s.folder)
+                    .and_then(|rest| rest.strip_prefix('-'))
+                    .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            });
+            if is_attempt && entry.path().is_dir() {
+                worlds.push(entry.path());
+            }
+        }
+    }
+    worlds.sort();
+    worlds
+}
+
+// Print total and per savestate disk usage of a TAS,
+// plus the attempt worlds it left in the saves folder
+pub fn print_tas_usage(tas: &Tas) {
+    let total = size_of(&tas.path);
+    let quota = match tas.quota {
+        Some(quota) => format!(" of {} quota", format_size(quota)),
+        None => String::new(),
+    };
+    console::write_line(&Color::Magenta, true, &format!("{}: {}{}", tas.name, format_size(total), quota));
+
+    let mut savestates: Vec<(PathBuf, u64)> = tas.get_savestates().into_iter()
+        .map(|s| { let size = size_of(&s); (s, size) })
+        .collect();
+    savestates.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    let names = tas.format_names(&savestates.iter().map(|(s, _)| s.clone()).collect::<Vec<_>>());
+    for (name, (_, size)) in names.iter().zip(&savestates) {
+        console::write_line(&Color::Cyan, false, &format!("  {} {:>10}", name, format_size(*size)));
+    }
+
+    let autosaves_folder = autosave::get_autosaves_folder(&tas.path);
+    let count = match autosave::get_autosaves(&tas.path) {
+        Ok(autosaves) => autosaves.len().to_string(),
+        Err(_) => "?".to_string(),
+    };
+    console::write_line(&Color::Yellow, false, &format!(
+        "  {} automatic snapshots: {}",
+        count,
+        format_size(size_of(&autosaves_folder))
+    ));
+
+    let attempts = get_attempt_worlds(tas);
+    let attempts_size: u64 = attempts.iter().map(|w| size_of(w)).sum();
+    console::write_line(&Color::Yellow, false, &format!(
+        "  {} attempt worlds in {}: {}",
+        attempts.len(),
+        tas.minecraft_folder.join("saves").display(),
+        format_size(attempts_size)
+    ));
+}
+
+// Print disk usage of every TAS under ~/.savestates/tases
+pub fn print_usage() {
+    let tases = dotfile::get_tases();
+    if tases.is_empty() {
+        console::write_line(&Color::Red, true, "No TAS files found");
+        return;
+    }
+    for tas in &tases {
+        print_tas_usage(tas);
+    }
+    let total = size_of(&dotfile::get_dotfile_path().join("tases"));
+    console::write_line(&Color::Magenta, true, &format!("Total: {}", format_size(total)));
+}
@@ -0,0 +1,125 @@
+use crate::console;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+use crossterm::style::Color;
+
+// Relative file path (with forward slashes) -> SHA-256 hex digest
+pub type Manifest = BTreeMap<String, String>;
+
+pub struct VerifyReport {
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.modified.is_empty()
+    }
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Hash every file in a folder
+pub fn hash_folder(folder: &Path) -> io::Result<Manifest> {
+    let mut manifest = Manifest::new();
+    for entry in WalkDir::new(folder).min_depth(1).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(folder).unwrap();
+        let key = relative.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        manifest.insert(key, hash_file(entry.path())?);
+    }
+    Ok(manifest)
+}
+
+// Manifests live in the TAS folder rather than in the savestate,
+// so they are never copied into the game's saves folder
+pub fn manifest_path(tas_folder: &Path, savestate_folder_name: &str) -> PathBuf {
+    tas_folder.join("checksums").join(format!("{}.json", savestate_folder_name))
+}
+
+// Record checksums for a savestate
+pub fn record_manifest(tas_folder: &Path, savestate: &Path) -> io::Result<()> {
+    let manifest = hash_folder(savestate)?;
+    let path = manifest_path(tas_folder, &savestate.file_name().unwrap().to_string_lossy());
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    serde_json::to_writer(File::create(path)?, &manifest)?;
+    Ok(())
+}
+
+pub fn read_manifest(tas_folder: &Path, savestate: &Path) -> Option<Manifest> {
+    let path = manifest_path(tas_folder, &savestate.file_name()?.to_string_lossy());
+    let file = File::open(path).ok()?;
+    serde_json::from_reader(file).ok()
+}
+
+pub fn remove_manifest(tas_folder: &Path, savestate: &Path) {
+    let path = manifest_path(tas_folder, &savestate.file_name().unwrap().to_string_lossy());
+    let _ = std::fs::remove_file(path);
+}
+
+// Re-hash a savestate and compare it with its recorded checksums.
+// Returns None if no checksums were recorded for it.
+pub fn verify_savestate(tas_folder: &Path, savestate: &Path) -> io::Result<Option<VerifyReport>> {
+    let expected = match read_manifest(tas_folder, savestate) {
+        Some(manifest) => manifest,
+        None => return Ok(None),
+    };
+    let actual = hash_folder(savestate)?;
+
+    let mut report = VerifyReport { missing: vec![], extra: vec![], modified: vec![] };
+    for (file, hash) in &expected {
+        match actual.get(file) {
+            None => report.missing.push(file.clone()),
+            Some(actual_hash) if actual_hash != hash => report.modified.push(file.clone()),
+            _ => {}
+        }
+    }
+    report.extra = actual.keys().filter(|f| !expected.contains_key(*f)).cloned().collect();
+
+    Ok(Some(report))
+}
+
+// Print the result of verifying one savestate. Returns true if it passed.
+pub fn print_report(name: &str, report: &io::Result<Option<VerifyReport>>) -> bool {
+    match report {
+        Ok(Some(report)) if report.is_ok() => {
+            console::write_line(&Color::Green, true, &format!("{}: OK", name));
+            true
+        }
+        Ok(Some(report)) => {
+            console::write_line(&Color::Red, true, &format!("{}: FAILED", name));
+            for file in &report.missing {
+                console::write_line(&Color::Red, false, &format!("  missing  {}", file));
+            }
+            for file in &report.modified {
+                console::write_line(&Color::Yellow, false, &format!("  modified {}", file));
+            }
+            for file in &report.extra {
+                console::write_line(&Color::Cyan, false, &format!("  extra    {}", file));
+            }
+            false
+        }
+        Ok(None) => {
+            console::write_line(&Color::Yellow, true, &format!("{}: no checksums recorded", name));
+            true
+        }
+        Err(e) => {
+            console::write_line(&Color::Red, true, &format!("{}: could not be read ({})", name, e));
+            false
+        }
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::ScratchTas;
+use savestates::autosave;
+use savestates::stats;
+use savestates::tas::Tas;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+// Save the world again, as the game would between snapshots. Saves
+// are dated the given number of minutes after the test started, so
+// they come after any snapshot taken before them.
+fn play(scratch: &ScratchTas, world: &str, minutes: u64) -> PathBuf {
+    scratch.add_world_saved_at(world, SystemTime::now() + Duration::from_secs(minutes * 60))
+}
+
+fn snapshot_folders(tas: &Tas) -> Vec<String> {
+    let mut folders: Vec<String> = std::fs::read_dir(autosave::get_autosaves_folder(&tas.path)).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    folders.sort();
+    folders
+}
+
+#[test]
+fn is_off_by_default() {
+    let json = r#"{"name":"old","minecraft_folder":"mc","path":"tas","num_savestates":0,"attempts":{}}"#;
+    let old: Tas = serde_json::from_str(json).unwrap();
+    assert_eq!(old.autosave_minutes, None);
+    let new = Tas::new("new".to_string(), PathBuf::from("mc"), PathBuf::from("tas"));
+    assert_eq!(new.autosave_minutes, None);
+}
+
+#[test]
+fn skips_worlds_saved_before_the_last_snapshot() {
+    let scratch = ScratchTas::new("autosave-unchanged");
+    let world = play(&scratch, "World", 0);
+    assert!(autosave::snapshot(&scratch.tas, &world).is_some());
+    assert!(autosave::snapshot(&scratch.tas, &world).is_none());
+
+    let world = play(&scratch, "World", 1);
+    assert!(autosave::snapshot(&scratch.tas, &world).is_some());
+    assert_eq!(autosave::get_autosaves(&scratch.tas.path).unwrap().len(), 2);
+}
+
+#[test]
+fn keeps_only_the_newest_snapshots() {
+    let mut scratch = ScratchTas::new("autosave-ring");
+    scratch.tas.autosave_keep = 3;
+    let mut taken = vec![];
+    for minutes in 0..5 {
+        let world = play(&scratch, "World", minutes + 1);
+        taken.push(autosave::snapshot(&scratch.tas, &world).unwrap().folder);
+    }
+
+    let kept: Vec<String> = autosave::get_autosaves(&scratch.tas.path).unwrap().into_iter().map(|a| a.folder).collect();
+    assert_eq!(kept, taken[2..]);
+    // The folders of dropped snapshots are deleted too
+    assert_eq!(snapshot_folders(&scratch.tas), taken[2..]);
+}
+
+#[test]
+fn promotes_snapshots_without_touching_attempts() {
+    let mut scratch = ScratchTas::new("autosave-promote");
+    let world = play(&scratch, "World", 1);
+    let autosave = autosave::snapshot(&scratch.tas, &world).unwrap();
+    stats::start_attempt(&scratch.tas.path, "playing");
+
+    autosave::promote(&mut scratch.tas, &autosave, "snapshot".to_string()).unwrap();
+    let savestate = &scratch.tas.savestates[0];
+    assert_eq!(savestate.created, autosave.created);
+    assert_eq!(savestate.created_from, None);
+    assert_eq!(savestate.segment_seconds, None);
+
+    let attempts = stats::get_attempts(&scratch.tas.path).unwrap();
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(attempts[0].savestate, "playing");
+    assert_eq!(attempts[0].ended_by, None);
+}
+
+#[test]
+fn leaves_an_unreadable_log_alone() {
+    let scratch = ScratchTas::new("autosave-corrupt");
+    let world = play(&scratch, "World", 1);
+    let log = scratch.tas.path.join("autosaves.json");
+    std::fs::write(&log, "[{\"folder\":").unwrap();
+
+    assert!(autosave::get_autosaves(&scratch.tas.path).is_err());
+    assert!(autosave::snapshot(&scratch.tas, &world).is_none());
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), "[{\"folder\":");
+    assert!(!autosave::get_autosaves_folder(&scratch.tas.path).exists());
+}
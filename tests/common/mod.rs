@@ -0,0 +1,54 @@
+// Each test file uses only some of these helpers
+#![allow(dead_code)]
+
+use savestates::dotfile;
+use savestates::nbt::{self, Tag};
+use savestates::tas::Tas;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// A TAS with its own Minecraft folder. Creating savestates writes to the
+// dotfile, so HOME points into the target folder for the whole test run.
+pub struct ScratchTas {
+    pub tas: Tas,
+    pub minecraft: PathBuf,
+}
+
+impl ScratchTas {
+    pub fn new(name: &str) -> ScratchTas {
+        let root = Path::new(env!("CARGO_TARGET_TMPDIR"));
+        std::env::set_var("HOME", root.join("home"));
+        let path = dotfile::get_dotfile_path().join("tases").join(name);
+        let minecraft = root.join(format!("minecraft-{}", name));
+        let _ = std::fs::remove_dir_all(&path);
+        let _ = std::fs::remove_dir_all(&minecraft);
+        std::fs::create_dir_all(path.join("savestates")).unwrap();
+        std::fs::create_dir_all(minecraft.join("saves")).unwrap();
+        ScratchTas { tas: Tas::new(name.to_string(), minecraft.clone(), path), minecraft }
+    }
+
+    pub fn add_world(&self, name: &str) -> PathBuf {
+        let world = self.minecraft.join("saves").join(name);
+        std::fs::create_dir_all(&world).unwrap();
+        std::fs::write(world.join("session.lock"), "").unwrap();
+        let data = Tag::Compound(vec![("LevelName".to_string(), Tag::String(name.to_string()))]);
+        nbt::write_file(world.join("level.dat"), "", &Tag::Compound(vec![("Data".to_string(), data)])).unwrap();
+        world
+    }
+
+    // Add a world the game last saved at the given time. Setting it
+    // explicitly keeps tests independent of the timestamp resolution.
+    pub fn add_world_saved_at(&self, name: &str, saved: SystemTime) -> PathBuf {
+        let world = self.add_world(name);
+        File::options().write(true).open(world.join("level.dat")).unwrap().set_modified(saved).unwrap();
+        world
+    }
+}
+
+impl Drop for ScratchTas {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.tas.path);
+        let _ = std::fs::remove_dir_all(&self.minecraft);
+    }
+}
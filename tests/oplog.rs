@@ -0,0 +1,18 @@
+mod common;
+
+use common::ScratchTas;
+use savestates::dotfile;
+use savestates::oplog::{self, Operation};
+
+#[test]
+fn leaves_an_unreadable_log_alone() {
+    let mut scratch = ScratchTas::new("oplog-corrupt");
+    let log = dotfile::get_dotfile_path().join("oplog.json");
+    std::fs::write(&log, "[{\"time\":").unwrap();
+
+    assert!(oplog::get_entries().is_err());
+    oplog::record(Operation::Create { tas: scratch.tas.name.clone(), folder: "folder".to_string() });
+    assert!(oplog::undo(&mut scratch.tas).is_err());
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), "[{\"time\":");
+    std::fs::remove_file(&log).unwrap();
+}
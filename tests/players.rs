@@ -0,0 +1,50 @@
+mod common;
+
+use common::ScratchTas;
+use savestates::nbt::Tag;
+use savestates::players::{self, PlayerHandling};
+
+fn player(health: f32) -> Tag {
+    Tag::Compound(vec![("Health".to_string(), Tag::Float(health))])
+}
+
+fn health(world: &std::path::Path, id: &str) -> Option<f32> {
+    players::list_players(world).into_iter().find(|p| p.id == id)?.health
+}
+
+#[test]
+fn swaps_two_players() {
+    let scratch = ScratchTas::new("players-swap");
+    let world = scratch.add_world("World");
+    players::write_player(&world, players::SINGLEPLAYER, player(20.0)).unwrap();
+    players::write_player(&world, "players/Alex", player(5.0)).unwrap();
+
+    let swap = PlayerHandling::Swap { first: players::SINGLEPLAYER.to_string(), second: "players/Alex".to_string() };
+    players::apply_handling(&world, &swap).unwrap();
+    assert_eq!(health(&world, players::SINGLEPLAYER), Some(5.0));
+    assert_eq!(health(&world, "players/Alex"), Some(20.0));
+}
+
+#[test]
+fn copies_without_swapping() {
+    let scratch = ScratchTas::new("players-copy");
+    let world = scratch.add_world("World");
+    players::write_player(&world, players::SINGLEPLAYER, player(20.0)).unwrap();
+    players::write_player(&world, "players/Alex", player(5.0)).unwrap();
+
+    let copy = PlayerHandling::Copy { from: "players/Alex".to_string(), to: players::SINGLEPLAYER.to_string() };
+    players::apply_handling(&world, &copy).unwrap();
+    assert_eq!(health(&world, players::SINGLEPLAYER), Some(5.0));
+    assert_eq!(health(&world, "players/Alex"), Some(5.0));
+}
+
+#[test]
+fn refuses_to_swap_with_a_missing_player() {
+    let scratch = ScratchTas::new("players-missing");
+    let world = scratch.add_world("World");
+    players::write_player(&world, "players/Alex", player(5.0)).unwrap();
+
+    let swap = PlayerHandling::Swap { first: "players/Alex".to_string(), second: "players/Steve".to_string() };
+    assert!(players::apply_handling(&world, &swap).is_err());
+    assert_eq!(health(&world, "players/Alex"), Some(5.0));
+}
@@ -0,0 +1,172 @@
+use savestates::rcon::{self, Packet, Rcon};
+use savestates::server;
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+const PASSWORD: &str = "hunter2";
+
+// A minimal RCON server that records the commands it receives.
+// It shuts down its listener after a stop command.
+struct MockServer {
+    port: u16,
+    commands: Arc<Mutex<Vec<String>>>,
+    thread: JoinHandle<()>,
+}
+
+fn handle_connection(mut stream: TcpStream, commands: &Mutex<Vec<String>>) -> bool {
+    let mut logged_in = false;
+    while let Ok(packet) = rcon::read_packet(&mut stream) {
+        match packet.kind {
+            rcon::LOGIN => {
+                logged_in = packet.payload == PASSWORD;
+                let id = if logged_in { packet.id } else { -1 };
+                rcon::write_packet(&mut stream, &Packet { id, kind: rcon::COMMAND, payload: String::new() }).unwrap();
+            }
+            rcon::COMMAND if logged_in => {
+                commands.lock().unwrap().push(packet.payload.clone());
+                let payload = format!("ran {}", packet.payload);
+                rcon::write_packet(&mut stream, &Packet { id: packet.id, kind: rcon::RESPONSE, payload }).unwrap();
+                if packet.payload == "stop" {
+                    return true;
+                }
+            }
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn start_mock_server() -> MockServer {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let commands = Arc::new(Mutex::new(Vec::new()));
+    let thread_commands = commands.clone();
+    let thread = thread::spawn(move || {
+        for stream in listener.incoming() {
+            if handle_connection(stream.unwrap(), &thread_commands) {
+                break;
+            }
+        }
+    });
+    MockServer { port, commands, thread }
+}
+
+fn unused_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+// A temporary server folder, deleted when the test ends
+struct ServerFolder(PathBuf);
+
+impl Deref for ServerFolder {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ServerFolder {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn server_folder(name: &str, port: u16) -> ServerFolder {
+    let dir = std::env::temp_dir().join(format!("savestates-rcon-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let properties = format!(
+        "#Minecraft server properties\nenable-rcon=true\nrcon.port={}\nrcon.password={}\nserver-port={}\nlevel-name=world\n",
+        port,
+        PASSWORD,
+        unused_port()
+    );
+    std::fs::write(dir.join("server.properties"), properties).unwrap();
+    ServerFolder(dir)
+}
+
+#[test]
+fn packet_round_trip() {
+    let mut bytes = Vec::new();
+    rcon::write_packet(&mut bytes, &Packet { id: 7, kind: rcon::COMMAND, payload: "list".to_string() }).unwrap();
+    assert_eq!(bytes.len(), 4 + 10 + 4);
+    assert_eq!(&bytes[0..4], &14i32.to_le_bytes());
+
+    let packet = rcon::read_packet(&mut bytes.as_slice()).unwrap();
+    assert_eq!(packet.id, 7);
+    assert_eq!(packet.kind, rcon::COMMAND);
+    assert_eq!(packet.payload, "list");
+}
+
+#[test]
+fn runs_commands_after_login() {
+    let mock = start_mock_server();
+    let mut rcon = Rcon::connect(&format!("127.0.0.1:{}", mock.port), PASSWORD).unwrap();
+    assert_eq!(rcon.command("time query daytime").unwrap(), "ran time query daytime");
+    assert_eq!(rcon.command("stop").unwrap(), "ran stop");
+    mock.thread.join().unwrap();
+    assert_eq!(*mock.commands.lock().unwrap(), vec!["time query daytime", "stop"]);
+}
+
+#[test]
+fn rejects_wrong_password() {
+    let mock = start_mock_server();
+    let error = Rcon::connect(&format!("127.0.0.1:{}", mock.port), "wrong").err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+}
+
+#[test]
+fn reads_rcon_settings_from_server_properties() {
+    let folder = server_folder("settings", 25580);
+    assert_eq!(server::rcon_settings(&folder), Some(("127.0.0.1:25580".to_string(), PASSWORD.to_string())));
+
+    std::fs::write(folder.join("server.properties"), "enable-rcon=false\nrcon.password=x\n").unwrap();
+    assert_eq!(server::rcon_settings(&folder), None);
+}
+
+#[test]
+fn pauses_saving_around_snapshots() {
+    let mock = start_mock_server();
+    let folder = server_folder("pause", mock.port);
+
+    let during = server::with_saving_paused(&folder, || mock.commands.lock().unwrap().clone());
+    assert_eq!(during, vec!["save-off", "save-all flush"]);
+    assert_eq!(*mock.commands.lock().unwrap(), vec!["save-off", "save-all flush", "save-on"]);
+}
+
+#[test]
+fn snapshots_without_a_running_server() {
+    let folder = server_folder("offline", unused_port());
+    assert_eq!(server::with_saving_paused(&folder, || 5), 5);
+}
+
+#[test]
+fn stops_the_server_and_waits() {
+    let mock = start_mock_server();
+    let folder = server_folder("stop", mock.port);
+
+    assert_eq!(server::stop_server(&folder), Ok(()));
+    mock.thread.join().unwrap();
+    assert_eq!(*mock.commands.lock().unwrap(), vec!["stop"]);
+}
+
+#[test]
+fn stopping_a_stopped_server_succeeds() {
+    let folder = server_folder("stopped", unused_port());
+    assert_eq!(server::stop_server(&folder), Ok(()));
+}
+
+#[test]
+fn detects_a_running_server() {
+    let mock = start_mock_server();
+    let folder = server_folder("running", mock.port);
+    assert!(server::is_running(&folder));
+
+    let stopped = server_folder("not-running", unused_port());
+    assert!(!server::is_running(&stopped));
+}
@@ -0,0 +1,157 @@
+use savestates::nbt::{self, Tag};
+use savestates::region::{self, Compression, Region, RegionFormat, SECTOR_SIZE};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("savestates-region-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn chunk_nbt(x: i32, z: i32) -> Vec<u8> {
+    let tag = Tag::Compound(vec![
+        ("xPos".to_string(), Tag::Int(x)),
+        ("zPos".to_string(), Tag::Int(z)),
+        ("Status".to_string(), Tag::String("minecraft:full".to_string())),
+    ]);
+    let mut bytes = Vec::new();
+    nbt::write(&mut bytes, "", &tag).unwrap();
+    bytes
+}
+
+fn compress(compression: Compression, data: &[u8]) -> Vec<u8> {
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        Compression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        Compression::Uncompressed => data.to_vec(),
+        Compression::Lz4 => {
+            // lz4-java block stream: one compressed block then an empty end block
+            let block = lz4_flex::block::compress(data);
+            let mut out = Vec::new();
+            out.extend_from_slice(b"LZ4Block");
+            out.push(0x20);
+            out.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&0u32.to_le_bytes());
+            out.extend_from_slice(&block);
+            out.extend_from_slice(b"LZ4Block");
+            out.push(0x10);
+            out.extend_from_slice(&[0; 12]);
+            out
+        }
+    }
+}
+
+// Build a region file holding the given (x, z, compression id, payload, timestamp) chunks
+fn build_region(chunks: &[(i32, i32, u8, Vec<u8>, u32)]) -> Vec<u8> {
+    let mut bytes = vec![0u8; SECTOR_SIZE * 2];
+    for (x, z, compression_id, payload, timestamp) in chunks {
+        let index = region::chunk_index(*x, *z);
+        let offset = bytes.len() / SECTOR_SIZE;
+        let mut chunk = Vec::new();
+        chunk.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        chunk.push(*compression_id);
+        chunk.extend_from_slice(payload);
+        let sectors = chunk.len().div_ceil(SECTOR_SIZE);
+        chunk.resize(sectors * SECTOR_SIZE, 0);
+        bytes.extend_from_slice(&chunk);
+
+        let location = ((offset as u32) << 8) | sectors as u32;
+        bytes[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+        bytes[SECTOR_SIZE + index * 4..SECTOR_SIZE + index * 4 + 4].copy_from_slice(&timestamp.to_be_bytes());
+    }
+    bytes
+}
+
+fn assert_chunk(region: &Region, x: i32, z: i32) {
+    let tag = region.read_chunk_nbt(x, z).unwrap().unwrap();
+    assert_eq!(tag.get("xPos"), Some(&Tag::Int(x)));
+    assert_eq!(tag.get("zPos"), Some(&Tag::Int(z)));
+}
+
+#[test]
+fn reads_every_compression_type() {
+    let types = [Compression::Gzip, Compression::Zlib, Compression::Uncompressed, Compression::Lz4];
+    let chunks: Vec<_> = types.iter().enumerate()
+        .map(|(i, c)| (i as i32, 3, c.id(), compress(*c, &chunk_nbt(i as i32, 3)), 1000 + i as u32))
+        .collect();
+    let region = Region::from_bytes(build_region(&chunks)).unwrap();
+
+    assert_eq!(region.chunks(), vec![(0, 3), (1, 3), (2, 3), (3, 3)]);
+    for (i, compression) in types.iter().enumerate() {
+        let x = i as i32;
+        assert_eq!(region.raw_chunk(x, 3).unwrap().unwrap().compression, *compression);
+        assert_eq!(region.timestamp(x, 3), 1000 + i as u32);
+        assert_chunk(&region, x, 3);
+    }
+}
+
+#[test]
+fn missing_chunks_and_empty_files() {
+    let region = Region::from_bytes(build_region(&[])).unwrap();
+    assert!(region.chunks().is_empty());
+    assert_eq!(region.location(5, 5), None);
+    assert_eq!(region.read_chunk(5, 5).unwrap(), None);
+
+    let empty = Region::from_bytes(Vec::new()).unwrap();
+    assert!(empty.chunks().is_empty());
+}
+
+#[test]
+fn absolute_coordinates_map_into_the_region() {
+    let payload = compress(Compression::Zlib, &chunk_nbt(-1, -32));
+    let region = Region::from_bytes(build_region(&[(31, 0, 2, payload, 7)])).unwrap();
+    assert!(region.location(-1, -32).is_some());
+    assert_eq!(region.timestamp(-1, -32), 7);
+    assert_eq!(region::chunk_index(-1, -32), 31);
+}
+
+#[test]
+fn rejects_corrupt_chunks() {
+    let mut bytes = build_region(&[(0, 0, 9, vec![1, 2, 3], 0)]);
+    let region = Region::from_bytes(bytes.clone()).unwrap();
+    assert!(region.raw_chunk(0, 0).is_err());
+
+    // Point a chunk past the end of the file
+    bytes[4..8].copy_from_slice(&((50u32 << 8) | 1).to_be_bytes());
+    let region = Region::from_bytes(bytes).unwrap();
+    assert!(region.raw_chunk(1, 0).is_err());
+}
+
+#[test]
+fn reads_external_chunks_from_disk() {
+    let dir = temp_dir("external");
+    let payload = compress(Compression::Zlib, &chunk_nbt(65, -1));
+    let path = dir.join("r.2.-1.mca");
+    // Region 2, -1 holds chunk 65, -1 at local 1, 31
+    std::fs::write(&path, build_region(&[(1, 31, 0x82, Vec::new(), 0)])).unwrap();
+    std::fs::write(dir.join("c.65.-1.mcc"), payload).unwrap();
+
+    let region = Region::open(&path).unwrap();
+    let raw = region.raw_chunk(1, 31).unwrap().unwrap();
+    assert!(raw.external);
+    assert_eq!(raw.compression, Compression::Zlib);
+    assert_chunk(&region, 65, -1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn parses_region_file_names() {
+    assert_eq!(region::region_coordinates(Path::new("region/r.-3.12.mca")), Some((-3, 12)));
+    assert_eq!(region::region_coordinates(Path::new("r.0.0.mcr")), Some((0, 0)));
+    assert_eq!(region::region_coordinates(Path::new("c.0.0.mcc")), None);
+    assert_eq!(region::region_coordinates(Path::new("r.a.0.mca")), None);
+    assert_eq!(RegionFormat::from_path(Path::new("r.0.0.mcr")), Some(RegionFormat::McRegion));
+    assert_eq!(RegionFormat::from_path(Path::new("r.0.0.mca")), Some(RegionFormat::Anvil));
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::ScratchTas;
+use savestates::route::{self, RouteSegment};
+use savestates::tas::{Savestate, Tas};
+use std::path::PathBuf;
+use chrono::Utc;
+
+fn savestate(id: usize, nickname: &str, created_from: Option<usize>) -> Savestate {
+    Savestate {
+        id,
+        nickname: nickname.to_string(),
+        folder: folder(id),
+        created: Utc::now(),
+        base: None,
+        partial: None,
+        players: vec![],
+        created_from: created_from.map(folder),
+        segment_seconds: None,
+        unverified_baseline: false,
+    }
+}
+
+fn folder(id: usize) -> String {
+    format!("route-{}", id)
+}
+
+fn segment(name: &str, savestate: usize) -> RouteSegment {
+    RouteSegment { name: name.to_string(), savestate: Some(savestate) }
+}
+
+fn route() -> Tas {
+    let mut tas = Tas::new("route".to_string(), PathBuf::from("minecraft"), PathBuf::from("tas"));
+    tas.savestates = vec![
+        savestate(0, "Spawn", None),
+        savestate(1, "Nether", Some(0)),
+        savestate(2, "Nether retry", Some(0)),
+        savestate(3, "Fortress", Some(1)),
+        savestate(4, "Fortress retry", Some(2)),
+        savestate(5, "Unrelated", None),
+    ];
+    tas.route = vec![segment("Spawn", 0), segment("Nether", 1), segment("Fortress", 3)];
+    tas
+}
+
+fn savestate_path(id: usize) -> PathBuf {
+    PathBuf::from("tas").join("savestates").join(folder(id))
+}
+
+#[test]
+fn finds_segments_through_ancestors() {
+    let tas = route();
+    assert_eq!(route::get_segment_of(&tas, &folder(0)), Some(0));
+    assert_eq!(route::get_segment_of(&tas, &folder(3)), Some(2));
+    // Retries belong to the segment they were created from
+    assert_eq!(route::get_segment_of(&tas, &folder(2)), Some(0));
+    assert_eq!(route::get_segment_of(&tas, &folder(4)), Some(0));
+    assert_eq!(route::get_segment_of(&tas, &folder(5)), None);
+    assert_eq!(route::get_segment_of(&tas, "deleted"), None);
+}
+
+#[test]
+fn loads_the_segment_after_the_last_loaded_one() {
+    let tas = route();
+    assert_eq!(route::get_next_savestate(&tas, None), Ok(savestate_path(0)));
+    assert_eq!(route::get_next_savestate(&tas, Some(&folder(1))), Ok(savestate_path(3)));
+    assert_eq!(route::get_next_savestate(&tas, Some(&folder(2))), Ok(savestate_path(1)));
+    assert!(route::get_next_savestate(&tas, Some(&folder(3))).is_err());
+    // Savestates outside the route don't restart it
+    assert!(route::get_next_savestate(&tas, Some(&folder(5))).is_err());
+
+    let empty = Tas::new("empty".to_string(), PathBuf::from("minecraft"), PathBuf::from("tas"));
+    assert!(route::get_next_savestate(&empty, None).is_err());
+}
+
+#[test]
+fn rejects_empty_and_taken_segment_names() {
+    let tas = route();
+    assert_eq!(route::check_segment_name(&tas, "  End  ", None), Ok("End".to_string()));
+    assert!(route::check_segment_name(&tas, "   ", None).is_err());
+    assert!(route::check_segment_name(&tas, "Nether", None).is_err());
+    assert!(route::check_segment_name(&tas, "Nether", Some(2)).is_err());
+    // Renaming a segment to its own name changes nothing
+    assert_eq!(route::check_segment_name(&tas, "Nether", Some(1)), Ok("Nether".to_string()));
+}
+
+#[test]
+fn promotes_savestates_to_segments() {
+    let mut scratch = ScratchTas::new("promote");
+    scratch.tas.savestates = route().savestates;
+    scratch.tas.route = route().route;
+
+    route::promote(&mut scratch.tas, 2, "Nether").unwrap();
+    route::promote(&mut scratch.tas, 4, " Stronghold ").unwrap();
+    assert!(route::promote(&mut scratch.tas, 5, " ").is_err());
+
+    let names: Vec<&str> = scratch.tas.route.iter().map(|segment| segment.name.as_str()).collect();
+    assert_eq!(names, vec!["Spawn", "Nether", "Fortress", "Stronghold"]);
+    assert_eq!(scratch.tas.route[1].savestate, Some(2));
+    assert_eq!(scratch.tas.route[3].savestate, Some(4));
+}
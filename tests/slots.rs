@@ -0,0 +1,69 @@
+mod common;
+
+use common::ScratchTas;
+use savestates::slots;
+
+fn current_nickname(scratch: &ScratchTas, slot: u8) -> Option<String> {
+    slots::get_current(&scratch.tas, slot).map(|info| info.nickname.clone())
+}
+
+fn rename_current(scratch: &mut ScratchTas, slot: u8, nickname: &str) {
+    let id = slots::get_current(&scratch.tas, slot).unwrap().id;
+    scratch.tas.savestates.iter_mut().find(|s| s.id == id).unwrap().nickname = nickname.to_string();
+}
+
+#[test]
+fn keeps_a_history_per_slot() {
+    let mut scratch = ScratchTas::new("slot-history");
+    scratch.add_world("World");
+    assert_eq!(slots::describe_slot(&scratch.tas, 1), "Slot 1: empty");
+    assert!(slots::save_to_slot(&mut scratch.tas, 10).is_err());
+
+    for nickname in ["first", "second", "third"] {
+        slots::save_to_slot(&mut scratch.tas, 1).unwrap();
+        rename_current(&mut scratch, 1, nickname);
+    }
+    slots::save_to_slot(&mut scratch.tas, 2).unwrap();
+    assert_eq!(current_nickname(&scratch, 1).as_deref(), Some("third"));
+    assert!(slots::describe_slot(&scratch.tas, 1).ends_with("2 in history)"));
+    assert!(slots::describe_slot(&scratch.tas, 2).ends_with("0 in history)"));
+}
+
+#[test]
+fn falls_back_when_savestates_are_deleted() {
+    let mut scratch = ScratchTas::new("slot-deleted");
+    scratch.add_world("World");
+    for nickname in ["first", "second", "third"] {
+        slots::save_to_slot(&mut scratch.tas, 0).unwrap();
+        rename_current(&mut scratch, 0, nickname);
+    }
+
+    scratch.tas.savestates.retain(|s| s.nickname != "third");
+    assert_eq!(current_nickname(&scratch, 0).as_deref(), Some("second"));
+    // Deleted savestates don't count towards the history
+    assert!(slots::describe_slot(&scratch.tas, 0).ends_with("1 in history)"));
+
+    scratch.tas.savestates.retain(|s| s.nickname != "first");
+    assert!(slots::describe_slot(&scratch.tas, 0).ends_with("0 in history)"));
+    assert_eq!(slots::get_slot_path(&scratch.tas, 0).unwrap(), scratch.tas.path.join("savestates").join(&scratch.tas.savestates[0].folder));
+}
+
+#[test]
+fn reverts_to_empty() {
+    let mut scratch = ScratchTas::new("slot-revert");
+    scratch.add_world("World");
+    for nickname in ["first", "second"] {
+        slots::save_to_slot(&mut scratch.tas, 5).unwrap();
+        rename_current(&mut scratch, 5, nickname);
+    }
+
+    let previous = slots::revert_slot(&mut scratch.tas, 5).unwrap().map(|info| info.nickname.clone());
+    assert_eq!(previous.as_deref(), Some("first"));
+    assert!(slots::revert_slot(&mut scratch.tas, 5).unwrap().is_none());
+    assert!(!scratch.tas.slots.contains_key(&5));
+    assert_eq!(slots::describe_slot(&scratch.tas, 5), "Slot 5: empty");
+    assert!(slots::revert_slot(&mut scratch.tas, 5).is_err());
+    assert!(slots::get_slot_path(&scratch.tas, 5).is_err());
+    // Reverting a slot keeps its savestates
+    assert_eq!(scratch.tas.savestates.len(), 2);
+}
@@ -0,0 +1,81 @@
+use savestates::stats::{self, Attempt, EndReason};
+use chrono::{Duration, Utc};
+use std::path::PathBuf;
+
+struct TasFolder(PathBuf);
+
+impl TasFolder {
+    fn new(name: &str) -> TasFolder {
+        let folder = std::env::temp_dir().join(format!("savestates-stats-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        TasFolder(folder)
+    }
+
+    fn write_attempts(&self, attempts: &[Attempt]) {
+        std::fs::write(self.0.join("attempts.json"), serde_json::to_string(attempts).unwrap()).unwrap();
+    }
+}
+
+impl Drop for TasFolder {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn open_attempt(hours_ago: i64) -> Attempt {
+    Attempt {
+        savestate: "1-start".to_string(),
+        loaded: Utc::now() - Duration::hours(hours_ago),
+        ended: None,
+        ended_by: None,
+    }
+}
+
+#[test]
+fn times_attempts_until_the_next_load() {
+    let folder = TasFolder::new("timed");
+    assert!(stats::get_attempts(&folder.0).unwrap().is_empty());
+    stats::start_attempt(&folder.0, "1-start");
+    let attempt = stats::end_attempt(&folder.0, EndReason::Load).unwrap();
+    assert_eq!(attempt.ended_by, Some(EndReason::Load));
+    assert!(attempt.duration().is_some());
+    // Nothing is open any more
+    assert!(stats::end_attempt(&folder.0, EndReason::Exit).is_none());
+}
+
+#[test]
+fn abandons_attempts_left_open_for_too_long() {
+    let folder = TasFolder::new("stale");
+    folder.write_attempts(&[open_attempt(72)]);
+    assert!(stats::end_attempt(&folder.0, EndReason::Create { folder: "2-next".to_string() }).is_none());
+
+    let attempts = stats::get_attempts(&folder.0).unwrap();
+    assert_eq!(attempts[0].ended_by, Some(EndReason::Abandoned));
+    assert_eq!(attempts[0].duration(), None);
+}
+
+#[test]
+fn abandons_the_attempt_of_a_previous_session() {
+    let folder = TasFolder::new("abandon");
+    folder.write_attempts(&[open_attempt(0)]);
+    stats::abandon_attempt(&folder.0);
+    assert!(stats::end_attempt(&folder.0, EndReason::Load).is_none());
+
+    let attempts = stats::get_attempts(&folder.0).unwrap();
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(attempts[0].ended_by, Some(EndReason::Abandoned));
+}
+
+#[test]
+fn leaves_unreadable_logs_alone() {
+    let folder = TasFolder::new("corrupt");
+    let log = folder.0.join("attempts.json");
+    std::fs::write(&log, "[{\"savestate\":").unwrap();
+    assert!(stats::get_attempts(&folder.0).is_err());
+
+    stats::start_attempt(&folder.0, "1-start");
+    stats::end_attempt(&folder.0, EndReason::Load);
+    stats::abandon_attempt(&folder.0);
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), "[{\"savestate\":");
+}
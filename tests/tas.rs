@@ -0,0 +1,67 @@
+mod common;
+
+use common::ScratchTas;
+use savestates::tas::{self, Tas};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn fills_templates_in_one_pass() {
+    let values = [("tas", "any%"), ("nickname", "{tas} {id}"), ("id", "3")];
+    assert_eq!(tas::fill_template("{tas} #{id} {nickname}", &values), "any% #3 {tas} {id}");
+    assert_eq!(tas::fill_template("{unknown} {tas", &values), "{unknown} {tas");
+    assert_eq!(tas::fill_template("{{tas}}", &values), "{any%}");
+}
+
+#[test]
+fn keeps_world_names_of_older_tas_files() {
+    let json = r#"{"name":"old","minecraft_folder":"mc","path":"tas","num_savestates":0,"attempts":{}}"#;
+    let old: Tas = serde_json::from_str(json).unwrap();
+    assert_eq!(old.level_name_template, None);
+
+    let new = Tas::new("new".to_string(), PathBuf::from("mc"), PathBuf::from("tas"));
+    assert_eq!(new.level_name_template.as_deref(), Some(tas::DEFAULT_LEVEL_NAME_TEMPLATE));
+}
+
+#[test]
+fn times_each_savestate_from_the_one_before() {
+    let mut scratch = ScratchTas::new("two-creates");
+    let world = scratch.add_world("World");
+    let first = scratch.tas.create_savestate(world.clone(), "first".to_string()).unwrap();
+    scratch.tas.create_savestate(world, "second".to_string()).unwrap();
+
+    let first_name = first.file_name().unwrap().to_string_lossy().to_string();
+    assert_eq!(scratch.tas.savestates[0].created_from, None);
+    assert_eq!(scratch.tas.savestates[1].created_from, Some(first_name));
+    assert!(scratch.tas.savestates[1].segment_seconds.is_some());
+}
+
+#[test]
+fn numbers_quick_saves_after_the_highest() {
+    let mut scratch = ScratchTas::new("quick-saves");
+    assert_eq!(scratch.tas.next_quick_save_nickname(), "quick 1");
+    scratch.add_world_saved_at("Old", SystemTime::now() - Duration::from_secs(60));
+    scratch.add_world_saved_at("Latest", SystemTime::now());
+    let savestate = scratch.tas.quick_save().unwrap();
+    assert_eq!(scratch.tas.savestates[0].nickname, "quick 1");
+    assert!(savestate.file_name().unwrap().to_string_lossy().ends_with("quick 1"));
+
+    scratch.tas.savestates[0].nickname = "quick 3".to_string();
+    scratch.tas.create_savestate(scratch.minecraft.join("saves").join("Old"), "quicksilver".to_string()).unwrap();
+    scratch.tas.create_savestate(scratch.minecraft.join("saves").join("Old"), "quick 10 again".to_string()).unwrap();
+    assert_eq!(scratch.tas.next_quick_save_nickname(), "quick 4");
+}
+
+#[test]
+fn lists_savestates_newest_first_by_id() {
+    let mut scratch = ScratchTas::new("savestate-order");
+    let world = scratch.add_world("World");
+    let first = scratch.tas.create_savestate(world.clone(), "first".to_string()).unwrap();
+    let second = scratch.tas.create_savestate(world, "second".to_string()).unwrap();
+    let untracked = scratch.tas.path.join("savestates").join("untracked");
+    std::fs::create_dir(&untracked).unwrap();
+    // Touching an older savestate doesn't move it up
+    std::fs::write(first.join("touched"), "").unwrap();
+
+    assert_eq!(scratch.tas.get_savestates(), vec![second, first, untracked]);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::ScratchTas;
+use savestates::trash::{self, TrashKind};
+
+#[test]
+fn leaves_an_unreadable_index_alone() {
+    let scratch = ScratchTas::new("trash-corrupt");
+    let world = scratch.add_world("World");
+    std::fs::create_dir_all(trash::get_trash_path()).unwrap();
+    let index = trash::get_trash_path().join("trash.json");
+    std::fs::write(&index, "[{\"id\":").unwrap();
+
+    assert!(trash::get_entries().is_err());
+    assert!(trash::move_to_trash(&world, TrashKind::World).is_err());
+    assert!(trash::purge_older_than(0).is_err());
+    assert!(trash::empty().is_err());
+    // The world wasn't moved and the index wasn't rewritten
+    assert!(world.join("level.dat").exists());
+    assert_eq!(std::fs::read_to_string(&index).unwrap(), "[{\"id\":");
+    std::fs::remove_file(&index).unwrap();
+}
+
+#[test]
+fn keeps_only_the_latest_load_backup() {
+    let scratch = ScratchTas::new("trash-load-backup");
+    let first = scratch.add_world("First");
+    trash::replace_load_backup(&scratch.tas.path, std::slice::from_ref(&first)).unwrap();
+    assert!(!first.exists());
+
+    let second = scratch.add_world("Second");
+    let second_id = trash::replace_load_backup(&scratch.tas.path, std::slice::from_ref(&second)).unwrap();
+    let backups: Vec<String> = std::fs::read_dir(trash::get_load_backup_path(&scratch.tas.path)).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(backups, vec![second_id.clone()]);
+    assert!(!scratch.tas.path.join("load-backup.previous").exists());
+
+    trash::restore_load_backup(&scratch.tas.path, &second_id, std::slice::from_ref(&second)).unwrap();
+    assert!(second.join("level.dat").exists());
+}